@@ -1,10 +1,16 @@
+use std::fmt::{self, Display};
+
 use toml_edit::{Document, Item};
 
+use crate::directives::Directives;
 use crate::ordering::OrderSectionKeysByGroupAlphabetically;
 use crate::{
-    AppendLineAfterSection, InlineTableWrap, KeyQuoteTrimmer, KeyTrimmer, OrderDependencies,
+    AlignEntries, AppendLineAfterSection, BlankLineFormatting, DependencyShorthand,
+    DottedKeyFormatting, InlineTableCollapse, InlineTableWrap, KeyQuoteTrimmer, KeyTrimmer,
+    MultilineStrings, OrderArrayOfTables, OrderArrayValues, OrderDependencies, OrderFeatures,
     OrderPackageSection, OrderSections, OrderTableKeysAlphabetically, SectionKeyNameTrimmer,
-    TableFormatting, TomlFormatter, WrapArray,
+    StringQuoteFormatting, TableFormatting, TableIndentation, TomlFormatter,
+    VersionRequirementFormatting, WrapArray, WrapDescription,
 };
 
 use crate::toml_config::TomlFormatConfig;
@@ -25,23 +31,45 @@ pub enum FormattingStage {
 
 /// The in memory representation of a Cargo.toml file.
 /// This is the main entry point for formatting a Cargo.toml file.
+///
+/// The document is kept with `\n` line endings, use [CargoToml::to_string] to write the file with
+/// the configured line endings, final newline and byte order mark.
 pub struct CargoToml {
     pub toml_document: Document,
+    /// The warnings reported by the rules during the last [CargoToml::format].
+    pub warnings: Vec<String>,
     rules: Vec<(bool, FormattingStage, Box<dyn TomlFormatter>)>,
     config: TomlFormatConfig,
+    /// The line ending used by most lines of the original contents.
+    line_ending: &'static str,
+    /// Whether the original contents start with a byte order mark.
+    has_bom: bool,
 }
 
 impl CargoToml {
+    const BOM: char = '\u{feff}';
+
     /// Loads the given toml contents and doesn't initialize default formatting rules.
     fn new(toml_contents: String, config: TomlFormatConfig) -> anyhow::Result<Self> {
+        let has_bom = toml_contents.starts_with(Self::BOM);
+        let crlf_count = toml_contents.matches("\r\n").count();
+        let lf_count = toml_contents.matches('\n').count() - crlf_count;
+        let line_ending = if crlf_count > lf_count { "\r\n" } else { "\n" };
+
+        // The rules only deal with `\n`, the line endings are restored when the document is written.
         let toml_document = toml_contents
+            .trim_start_matches(Self::BOM)
+            .replace("\r\n", "\n")
             .parse::<Document>()
             .map_err(|e| anyhow::anyhow!("Failed to parse toml. {e}"))?;
 
         Ok(Self {
             toml_document,
+            warnings: vec![],
             rules: vec![],
             config,
+            line_ending,
+            has_bom,
         })
     }
 
@@ -57,8 +85,33 @@ impl CargoToml {
     pub fn from_config(toml_contents: String, config: TomlFormatConfig) -> anyhow::Result<Self> {
         let mut toml = Self::new(toml_contents, config.clone())?;
 
+        if config.collapse_tables && config.wrap_table.is_some() {
+            toml.add_format_rule(
+                FormattingStage::BeforeFormatting,
+                InlineTableCollapse::default(),
+            );
+        }
+
+        if config.dotted_keys.is_some() {
+            toml.add_format_rule(
+                FormattingStage::BeforeFormatting,
+                DottedKeyFormatting::default(),
+            );
+        }
+
+        if config.dependency_style.is_some() || config.dotted_workspace_dependencies {
+            toml.add_format_rule(FormattingStage::BeforeFormatting, DependencyShorthand);
+        }
+
+        if config.version_format.is_some() || config.lint_wildcard_versions {
+            toml.add_format_rule(
+                FormattingStage::BeforeFormatting,
+                VersionRequirementFormatting::default(),
+            );
+        }
+
         if config.order_sections {
-            toml.add_format_rule(FormattingStage::BeforeFormatting, OrderSections);
+            toml.add_format_rule(FormattingStage::BeforeFormatting, OrderSections::default());
         }
 
         if config.order_package_section {
@@ -73,14 +126,35 @@ impl CargoToml {
         }
 
         if config.order_section_keys_by_group_alphabetically {
-            toml.add_format_rule(FormattingStage::BeforeFormatting, OrderSectionKeysByGroupAlphabetically);
+            toml.add_format_rule(
+                FormattingStage::BeforeFormatting,
+                OrderSectionKeysByGroupAlphabetically,
+            );
+        }
+        if config.order_array_of_tables_by_name || config.order_array_of_tables_keys {
+            toml.add_format_rule(
+                FormattingStage::BeforeFormatting,
+                OrderArrayOfTables::default(),
+            );
         }
+
+        if config.order_features.is_some() || config.order_feature_values.is_some() {
+            toml.add_format_rule(FormattingStage::BeforeFormatting, OrderFeatures);
+        }
+
+        if !config.order_array_values.is_empty() {
+            toml.add_format_rule(FormattingStage::BeforeFormatting, OrderArrayValues);
+        }
+
         if config.order_dependencies_alphabetically {
             toml.add_format_rule(FormattingStage::BeforeFormatting, OrderDependencies);
         }
 
         if config.trim_quotes_table_keys {
-            toml.add_format_rule(FormattingStage::BeforeFormatting, KeyQuoteTrimmer);
+            toml.add_format_rule(
+                FormattingStage::BeforeFormatting,
+                KeyQuoteTrimmer::default(),
+            );
         }
 
         if config.trim_section_item_keys {
@@ -91,22 +165,55 @@ impl CargoToml {
             toml.add_format_rule(FormattingStage::BeforeFormatting, SectionKeyNameTrimmer);
         }
 
+        if config.use_multiline_strings {
+            toml.add_format_rule(
+                FormattingStage::BeforeFormatting,
+                MultilineStrings::default(),
+            );
+        }
+
+        if config.string_quote_style.is_some() {
+            toml.add_format_rule(
+                FormattingStage::BeforeFormatting,
+                StringQuoteFormatting::default(),
+            );
+        }
+
         if config.table_formatting {
             toml.add_format_rule(FormattingStage::WhileFormatting, TableFormatting);
         }
 
+        // Long inline tables are moved to their own section first, so only the arrays within the remaining inline tables are wrapped.
+        if config.wrap_table.is_some() {
+            toml.add_format_rule(FormattingStage::AfterFormatting, InlineTableWrap::new());
+        }
+
+        // Wrapped arrays are indented relative to their key, so keys are indented first.
+        if config.table_indent.is_some() {
+            toml.add_format_rule(FormattingStage::AfterFormatting, TableIndentation);
+        }
+
         if config.wrap_array.is_some() {
             toml.add_format_rule(FormattingStage::AfterFormatting, WrapArray);
         }
 
-        if config.wrap_table.is_some() {
-            toml.add_format_rule(FormattingStage::AfterFormatting, InlineTableWrap::new());
+        if config.wrap_description.is_some() {
+            toml.add_format_rule(FormattingStage::AfterFormatting, WrapDescription::default());
         }
 
         if config.add_newline_after_section {
             toml.add_format_rule(FormattingStage::AfterFormatting, AppendLineAfterSection);
         }
 
+        if config.blank_lines.is_some() {
+            toml.add_format_rule(FormattingStage::AfterFormatting, BlankLineFormatting);
+        }
+
+        // Blank lines separate the groups of keys that are aligned.
+        if config.align_keys || config.align_comments {
+            toml.add_format_rule(FormattingStage::AfterFormatting, AlignEntries);
+        }
+
         Ok(toml)
     }
 
@@ -119,6 +226,8 @@ impl CargoToml {
     /// This iterates all rules and applies rules in order of their stage.
     pub fn format(&mut self) -> anyhow::Result<()> {
         let mut toml_document = self.toml_document.clone();
+        let directives = Directives::from_document(&toml_document);
+        self.warnings.clear();
 
         let mut iter_stage = |filter_stage: FormattingStage| -> anyhow::Result<()> {
             for (enabled, _, rule) in self
@@ -127,10 +236,14 @@ impl CargoToml {
                 .filter(|(_, stage, _)| *stage == filter_stage)
             {
                 if *enabled {
+                    rule.set_directives(&directives);
+
                     match rule.visit_document(&mut toml_document, &self.config) {
                         Ok(_) => {}
                         Err(e) => anyhow::bail!("Error: {:?}", e),
                     }
+
+                    self.warnings.extend(rule.take_warnings());
                 }
             }
             Ok(())
@@ -140,11 +253,21 @@ impl CargoToml {
         iter_stage(FormattingStage::WhileFormatting)?;
         iter_stage(FormattingStage::AfterFormatting)?;
 
+        // The items marked by a `# toml-fmt:` directive are put back as they were written.
+        if !directives.is_empty() {
+            directives.restore(&self.toml_document, &mut toml_document);
+        }
+
         self.toml_document = toml_document;
 
         Ok(())
     }
 
+    /// Returns the line ending of the formatted file, see [TomlFormatConfig::line_ending].
+    pub fn line_ending(&self) -> &'static str {
+        self.config.line_ending.as_str(self.line_ending)
+    }
+
     /// Returns the dependencies section of this document.
     pub fn dependencies(&mut self) -> anyhow::Result<&mut Item> {
         self.toml_document
@@ -152,3 +275,24 @@ impl CargoToml {
             .ok_or_else(|| anyhow::anyhow!("Dependencies tag not found in toml document"))
     }
 }
+
+impl Display for CargoToml {
+    /// Writes the document as the file contents, with the configured line endings, final newline and byte order mark.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut contents = self.toml_document.to_string();
+
+        if self.config.final_newline {
+            let trimmed_len = contents.trim_end().len();
+            contents.truncate(trimmed_len);
+            if !contents.is_empty() {
+                contents.push('\n');
+            }
+        }
+
+        if self.has_bom && !self.config.strip_bom {
+            write!(f, "{}", Self::BOM)?;
+        }
+
+        write!(f, "{}", contents.replace('\n', self.line_ending()))
+    }
+}
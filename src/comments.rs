@@ -0,0 +1,594 @@
+use std::collections::HashMap;
+
+use toml_edit::{Array, Decor, Document, Item, Table};
+
+use crate::toml_config::BlankLines;
+
+/// The comments of a key, value or section header, extracted from their [Decor].
+///
+/// ```toml
+/// # A leading comment.
+/// # Another leading comment.
+/// a = 1 # The trailing comment.
+/// ```
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Comments {
+    /// The comment lines above the item.
+    pub leading: Vec<String>,
+    /// The comment after the item, on the same line.
+    pub trailing: Option<String>,
+}
+
+impl Comments {
+    /// Extracts the comments from the decor, the whitespace around them is discarded.
+    pub fn from_decor(decor: &Decor) -> Self {
+        let leading = comment_lines(decor.prefix().unwrap_or_default());
+        let (trailing, _) = split_comment_lines(decor.suffix().unwrap_or_default());
+
+        Self { leading, trailing }
+    }
+
+    /// Returns whether there are no comments.
+    pub fn is_empty(&self) -> bool {
+        self.leading.is_empty() && self.trailing.is_none()
+    }
+
+    /// Returns the prefix with each leading comment on its own line, e.g. `# a\n# b\n`.
+    pub fn prefix(&self) -> String {
+        self.leading
+            .iter()
+            .map(|line| format!("{line}\n"))
+            .collect()
+    }
+
+    /// Returns the suffix with the trailing comment separated by one space, e.g. ` # a`.
+    pub fn suffix(&self) -> String {
+        match &self.trailing {
+            Some(comment) => format!(" {comment}"),
+            None => String::new(),
+        }
+    }
+}
+
+/// The comments within an array, which are kept when the array is reformatted.
+///
+/// ```toml
+/// a = [
+///     # A leading comment of "a".
+///     "a", # The trailing comment of "a".
+///     # A closing comment.
+/// ]
+/// ```
+#[derive(Debug, Default)]
+pub struct ArrayComments {
+    /// The comments of each value.
+    pub values: Vec<Comments>,
+    /// The comment lines before the closing bracket.
+    pub closing: Vec<String>,
+}
+
+impl ArrayComments {
+    /// Collects the comments from the decor of the array values and the array trailing.
+    pub fn from_array(array: &Array) -> Self {
+        let len = array.len();
+        let mut comments = Self {
+            values: vec![Comments::default(); len],
+            closing: vec![],
+        };
+
+        for (idx, value) in array.iter().enumerate() {
+            let decor = value.decor();
+
+            // The first line of a prefix belongs to the previous value, or to the opening bracket for the first value.
+            let (same_line, lines) = split_comment_lines(decor.prefix().unwrap_or_default());
+            match idx.checked_sub(1) {
+                Some(previous) => comments.set_trailing(previous, same_line),
+                None => comments.values[idx].leading.extend(same_line),
+            }
+            comments.values[idx].leading.extend(lines);
+
+            // Comments in a suffix are either before a comma or, for the last value, before the closing bracket.
+            let (same_line, lines) = split_comment_lines(decor.suffix().unwrap_or_default());
+            comments.set_trailing(idx, same_line);
+            match comments.values.get_mut(idx + 1) {
+                Some(next) => next.leading.extend(lines),
+                None => comments.closing.extend(lines),
+            }
+        }
+
+        let (same_line, lines) = split_comment_lines(array.trailing());
+        match len.checked_sub(1) {
+            Some(last) => comments.set_trailing(last, same_line),
+            None => comments.closing.extend(same_line),
+        }
+        comments.closing.extend(lines);
+
+        comments
+    }
+
+    /// Returns whether the array doesn't contain any comments.
+    pub fn is_empty(&self) -> bool {
+        self.values.iter().all(Comments::is_empty) && self.closing.is_empty()
+    }
+
+    /// Writes the array values each on their own line together with the comments.
+    ///
+    /// The closing bracket is indented with `closing_indent`, the values one `indent` deeper.
+    /// A trailing comma is added when `trailing_comma` is set, otherwise the trailing comma is kept as it is.
+    pub fn wrap_array(
+        &self,
+        array: &mut Array,
+        indent: &str,
+        closing_indent: &str,
+        trailing_comma: bool,
+    ) {
+        let indent = &format!("{closing_indent}{indent}");
+
+        if trailing_comma && !array.is_empty() {
+            array.set_trailing_comma(true);
+        }
+
+        let len = array.len();
+        let trailing =
+            |idx: usize| -> String { self.values.get(idx).map_or(String::new(), Comments::suffix) };
+        let indented_lines = |lines: &[String]| -> String {
+            lines
+                .iter()
+                .map(|line| format!("{indent}{line}\n"))
+                .collect()
+        };
+
+        for (idx, value) in array.iter_mut().enumerate() {
+            let previous = match idx.checked_sub(1) {
+                Some(previous) => trailing(previous),
+                None => String::new(),
+            };
+            let leading = self
+                .values
+                .get(idx)
+                .map_or(String::new(), |comments| indented_lines(&comments.leading));
+
+            value
+                .decor_mut()
+                .set_prefix(format!("{previous}\n{leading}{indent}"));
+            value.decor_mut().set_suffix("");
+        }
+
+        let closing = match len.checked_sub(1) {
+            Some(last) => format!(
+                "{}\n{}{closing_indent}",
+                trailing(last),
+                indented_lines(&self.closing)
+            ),
+            None => format!("\n{}{closing_indent}", indented_lines(&self.closing)),
+        };
+
+        // Without a trailing comma, the closing comments are part of the suffix of the last value.
+        let trailing_comma = array.trailing_comma();
+        match array.iter_mut().last() {
+            Some(last) if !trailing_comma => {
+                last.decor_mut().set_suffix(closing);
+                array.set_trailing("");
+            }
+            _ => array.set_trailing(&closing),
+        }
+    }
+
+    /// Sets the trailing comment of a value, a second comment on the same line becomes a leading comment of the next value.
+    fn set_trailing(&mut self, idx: usize, comment: Option<String>) {
+        if let Some(comment) = comment {
+            match &mut self.values[idx].trailing {
+                Some(_) => match self.values.get_mut(idx + 1) {
+                    Some(next) => next.leading.push(comment),
+                    None => self.closing.push(comment),
+                },
+                trailing => *trailing = Some(comment),
+            }
+        }
+    }
+}
+
+/// Returns whether the decor contains any comment.
+pub fn has_comments(decor: &Decor) -> bool {
+    [decor.prefix(), decor.suffix()]
+        .into_iter()
+        .flatten()
+        .any(|decor| !comment_lines(decor).is_empty())
+}
+
+/// The comments above the section headers that don't belong to the section, restored after the sections are moved.
+///
+/// Comments separated from a header by a blank line belong to the end of the previous section,
+/// or to the file header when it is the first section.
+///
+/// ```toml
+/// # The file header.
+///
+/// # The leading comment of the package section.
+/// [package]
+/// name = "a"
+/// # A dangling comment at the end of the package section.
+///
+/// [dependencies]
+/// ```
+#[derive(Debug, Default)]
+pub struct SectionComments {
+    /// The comments at the top of the document.
+    pub header: Vec<String>,
+    /// The dangling comments at the end of each section, by the path of the section.
+    pub dangling: HashMap<HeaderPath, Vec<String>>,
+}
+
+/// The path of a section header, the index is set for an entry of an array of tables.
+pub type HeaderPath = Vec<(String, Option<usize>)>;
+
+impl SectionComments {
+    /// Removes the file header and dangling comments from the section headers of the document.
+    pub fn detach(document: &mut Document) -> Self {
+        let mut comments = Self::default();
+        let paths = header_paths(document);
+
+        for (idx, path) in paths.iter().enumerate() {
+            let table = match header_mut(document, path) {
+                Some(table) => table,
+                None => continue,
+            };
+
+            let (dangling, rest) = split_dangling(table.decor().prefix().unwrap_or_default());
+            if dangling.is_empty() {
+                continue;
+            }
+
+            table.decor_mut().set_prefix(rest);
+
+            match idx.checked_sub(1) {
+                Some(previous) => {
+                    comments.dangling.insert(paths[previous].clone(), dangling);
+                }
+                None => comments.header = dangling,
+            }
+        }
+
+        comments
+    }
+
+    /// Places the file header above the first section and the dangling comments after their section.
+    pub fn attach(mut self, document: &mut Document) {
+        let paths = header_paths(document);
+
+        for (idx, path) in paths.iter().enumerate() {
+            let comments = match idx.checked_sub(1) {
+                Some(previous) => self.dangling.remove(&paths[previous]),
+                None => Some(std::mem::take(&mut self.header)),
+            };
+
+            let comments = match comments.filter(|comments| !comments.is_empty()) {
+                Some(comments) => comments,
+                None => continue,
+            };
+
+            if let Some(table) = header_mut(document, path) {
+                let prefix = table.decor().prefix().unwrap_or("\n").to_string();
+                let separator = if prefix.starts_with('\n') { "" } else { "\n" };
+                table
+                    .decor_mut()
+                    .set_prefix(format!("{}{separator}{prefix}", lines(&comments)));
+            }
+        }
+
+        // The dangling comments of the last section are placed at the end of the document.
+        let last = paths.last().and_then(|path| self.dangling.remove(path));
+        if let Some(comments) = last.filter(|comments| !comments.is_empty()) {
+            let trailing = document.trailing().to_string();
+            document.set_trailing(&format!("{}{trailing}", lines(&comments)));
+        }
+    }
+}
+
+/// Returns the paths of the tables with a header in the order they are displayed.
+pub(crate) fn header_paths(document: &Document) -> Vec<HeaderPath> {
+    fn visit(
+        table: &Table,
+        path: &mut HeaderPath,
+        position: &mut usize,
+        paths: &mut Vec<(usize, HeaderPath)>,
+    ) {
+        if let Some(table_position) = table.position() {
+            *position = table_position;
+        }
+
+        let is_visible = !path.is_empty()
+            && (path.last().is_some_and(|(_, idx)| idx.is_some())
+                || !(table.is_implicit() && table.get_values().is_empty()));
+        if is_visible {
+            paths.push((*position, path.clone()));
+        }
+
+        for (key, item) in table.iter() {
+            match item {
+                Item::Table(sub_table) if !sub_table.is_dotted() => {
+                    path.push((key.to_string(), None));
+                    visit(sub_table, path, position, paths);
+                    path.pop();
+                }
+                Item::ArrayOfTables(tables) => {
+                    for (idx, sub_table) in tables.iter().enumerate() {
+                        path.push((key.to_string(), Some(idx)));
+                        visit(sub_table, path, position, paths);
+                        path.pop();
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    let mut paths = vec![];
+    visit(document.as_table(), &mut vec![], &mut 0, &mut paths);
+
+    // The same stable sort as used when the document is displayed.
+    paths.sort_by_key(|(position, _)| *position);
+    paths.into_iter().map(|(_, path)| path).collect()
+}
+
+/// Returns the table with a header at the path, the root of the document for an empty path.
+pub(crate) fn header_ref<'a>(document: &'a Document, path: &HeaderPath) -> Option<&'a Table> {
+    let mut table = document.as_table();
+
+    for (key, idx) in path {
+        table = match (table.get(key)?, idx) {
+            (Item::Table(sub_table), None) => sub_table,
+            (Item::ArrayOfTables(tables), Some(idx)) => tables.get(*idx)?,
+            _ => return None,
+        };
+    }
+
+    Some(table)
+}
+
+/// Returns the table with a header at the path, see [header_ref].
+pub(crate) fn header_mut<'a>(
+    document: &'a mut Document,
+    path: &HeaderPath,
+) -> Option<&'a mut Table> {
+    let mut table = document.as_table_mut();
+
+    for (key, idx) in path {
+        table = match (table.get_mut(key)?, idx) {
+            (Item::Table(sub_table), None) => sub_table,
+            (Item::ArrayOfTables(tables), Some(idx)) => tables.get_mut(*idx)?,
+            _ => return None,
+        };
+    }
+
+    Some(table)
+}
+
+/// Splits the comments before the last blank line of a prefix from the rest of the prefix,
+/// e.g. `# a\n\n# b\n` returns `["# a"]` and `\n# b\n`.
+fn split_dangling(prefix: &str) -> (Vec<String>, String) {
+    let lines = prefix.split('\n').collect::<Vec<&str>>();
+
+    // The last line is the indentation before the header, which is never blank line.
+    let last_blank_line = lines[..lines.len() - 1]
+        .iter()
+        .rposition(|line| line.trim().is_empty());
+
+    match last_blank_line {
+        Some(idx) => (
+            lines[..idx]
+                .iter()
+                .filter_map(|line| as_comment(line))
+                .collect(),
+            lines[idx..].join("\n"),
+        ),
+        None => (vec![], prefix.to_string()),
+    }
+}
+
+fn lines(comments: &[String]) -> String {
+    comments.iter().map(|line| format!("{line}\n")).collect()
+}
+
+/// Splits the comment on the first line of the decor from the comment lines that follow,
+/// e.g. ` # a\n    # b\n    ` returns `# a` and `["# b"]`.
+///
+/// The first line of a suffix is on the same line as the item it decorates.
+pub fn split_comment_lines(decor: &str) -> (Option<String>, Vec<String>) {
+    let mut lines = decor.split('\n');
+
+    let same_line = lines.next().and_then(as_comment);
+    let lines = lines.filter_map(as_comment).collect();
+
+    (same_line, lines)
+}
+
+/// Returns all comment lines of the decor without the whitespace and blank lines around them,
+/// e.g. `\n  # a\n\n  # b\n  ` returns `["# a", "# b"]`.
+pub fn comment_lines(decor: &str) -> Vec<String> {
+    decor.split('\n').filter_map(as_comment).collect()
+}
+
+/// Splits the blank lines before the first comment of a prefix from the comments,
+/// e.g. `\n\n# a\n\n# b\n` returns `\n\n` and `# a\n\n# b\n`.
+///
+/// A prefix without comments is returned as a whole as its blank lines.
+pub fn split_leading_blank_lines(prefix: &str) -> (&str, &str) {
+    let mut start = 0;
+
+    for line in prefix.split_inclusive('\n') {
+        if as_comment(line).is_some() {
+            return prefix.split_at(start);
+        }
+        start += line.len();
+    }
+
+    (prefix, "")
+}
+
+/// Trims the whitespace and blank lines of a prefix, each comment is kept on its own line.
+///
+/// Comment blocks stay separated by a single blank line, `\n  # a\n\n\n  # b\n  ` becomes `# a\n\n# b\n`.
+pub fn trim_prefix(prefix: &str) -> String {
+    let mut trimmed = String::new();
+    let mut blank_line = false;
+
+    for line in prefix.split('\n') {
+        match as_comment(line) {
+            Some(comment) => {
+                if blank_line && !trimmed.is_empty() {
+                    trimmed.push('\n');
+                }
+                trimmed.push_str(&comment);
+                trimmed.push('\n');
+                blank_line = false;
+            }
+            None => blank_line = true,
+        }
+    }
+
+    trimmed
+}
+
+/// Trims the whitespace and blank lines of the prefix of a section header like [trim_prefix],
+/// but keeps the blank line between the comments that don't belong to the section and the section's own comments.
+///
+/// `# a\n\n\n  # b\n` becomes `# a\n\n# b\n`.
+pub fn trim_header_prefix(prefix: &str) -> String {
+    let (dangling, rest) = split_dangling(prefix);
+
+    if dangling.is_empty() {
+        trim_prefix(&rest)
+    } else {
+        format!("{}\n{}", lines(&dangling), trim_prefix(&rest))
+    }
+}
+
+/// Trims the whitespace of a prefix like [trim_prefix], but keeps the blank lines.
+///
+/// `\n  # a\n\n  # b\n  ` becomes `\n# a\n\n# b\n`.
+pub fn trim_prefix_lines(prefix: &str) -> String {
+    let lines = prefix.split('\n').collect::<Vec<&str>>();
+
+    // The last line is the indentation before the item.
+    lines[..lines.len() - 1]
+        .iter()
+        .map(|line| format!("{}\n", as_comment(line).unwrap_or_default()))
+        .collect()
+}
+
+/// Sets the blank lines of a prefix as configured by the [BlankLines] policy.
+///
+/// `leading` overrides the number of blank lines before the first comment or item, e.g. none after a section header.
+/// Blank lines between comments are kept up to [BlankLines::max_consecutive], and a blank line that separates
+/// comments from a section header is kept when `is_header` so the comments stay with the previous section.
+///
+/// A blank line between two blocks of comments is kept even when [BlankLines::max_consecutive] is 0,
+/// removing it would merge the comments of two items into one block.
+pub fn set_blank_lines(
+    prefix: &str,
+    leading: Option<usize>,
+    is_header: bool,
+    policy: &BlankLines,
+) -> String {
+    let lines = prefix.split('\n').collect::<Vec<&str>>();
+    let indent = lines[lines.len() - 1];
+
+    // The number of blank lines before the first comment, and each comment with the number of blank lines after it.
+    let mut blank_lines = 0;
+    let mut comments: Vec<(String, usize)> = vec![];
+    for line in &lines[..lines.len() - 1] {
+        match (as_comment(line), comments.last_mut()) {
+            (Some(comment), _) => comments.push((comment, 0)),
+            (None, Some((_, after))) => *after += 1,
+            (None, None) => blank_lines += 1,
+        }
+    }
+
+    // Only used where the blank lines separate comments, which keep at least one blank line.
+    let clamp = |count: usize| match count {
+        0 => 0,
+        count => count.min(policy.max_consecutive.max(1)),
+    };
+
+    let before = match (leading, comments.is_empty(), policy.before_comments) {
+        (Some(leading), _, _) => leading,
+        (None, false, Some(before)) => before,
+        (None, true, _) => blank_lines.min(policy.max_consecutive),
+        (None, false, None) => clamp(blank_lines),
+    };
+
+    let mut prefix = "\n".repeat(before);
+    let last = comments.len().saturating_sub(1);
+    for (idx, (comment, after)) in comments.into_iter().enumerate() {
+        let after = match (idx == last, is_header, policy.after_comments) {
+            (true, false, Some(after_comments)) => after_comments,
+            (true, false, None) => after.min(policy.max_consecutive),
+            _ => clamp(after),
+        };
+
+        // The comments are indented like the item they describe.
+        prefix.push_str(indent);
+        prefix.push_str(&comment);
+        prefix.push_str(&"\n".repeat(after + 1));
+    }
+
+    prefix.push_str(indent);
+    prefix
+}
+
+/// Trims the whitespace of a suffix, a comment is kept and separated from the item by one space.
+///
+/// `   # a  ` becomes ` # a`.
+pub fn trim_suffix(suffix: &str) -> String {
+    let (same_line, lines) = split_comment_lines(suffix);
+
+    same_line
+        .into_iter()
+        .chain(lines)
+        .map(|line| format!(" {line}"))
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+fn as_comment(line: &str) -> Option<String> {
+    let line = line.trim();
+    line.starts_with('#').then(|| line.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        split_comment_lines, split_dangling, trim_header_prefix, trim_prefix, trim_suffix,
+    };
+
+    #[test]
+    fn split_comments_from_whitespace() {
+        assert_eq!(
+            split_comment_lines(" # a\n    # b # c\n    "),
+            (Some("# a".to_string()), vec!["# b # c".to_string()])
+        );
+        assert_eq!(split_comment_lines("\n  "), (None, vec![]));
+    }
+
+    #[test]
+    fn split_dangling_comments() {
+        assert_eq!(
+            split_dangling("# a\n\n# b\n"),
+            (vec!["# a".to_string()], "\n# b\n".to_string())
+        );
+        assert_eq!(split_dangling("\n# b\n"), (vec![], "\n# b\n".to_string()));
+        assert_eq!(split_dangling("# b\n"), (vec![], "# b\n".to_string()));
+    }
+
+    #[test]
+    fn trim_keeps_comments() {
+        assert_eq!(trim_prefix("\n  # a\n\n\n  # b\n  "), "# a\n\n# b\n");
+        assert_eq!(trim_prefix("\n  # a\n  # b\n\n  "), "# a\n# b\n");
+        assert_eq!(trim_prefix("\n  \n  "), "");
+        assert_eq!(trim_header_prefix("# a\n\n\n  # b\n"), "# a\n\n# b\n");
+        assert_eq!(trim_header_prefix("\n  # b\n"), "# b\n");
+        assert_eq!(trim_suffix("   # a  "), " # a");
+        assert_eq!(trim_suffix("   "), "");
+    }
+}
@@ -0,0 +1,165 @@
+use std::{cmp::Ordering, iter::Peekable, str::Chars};
+
+use serde::{Deserialize, Serialize};
+
+/// The collation used by all ordering rules to compare keys and values.
+///
+/// The options can be combined, e.g. a case-insensitive natural sort.
+/// Strings that compare equal under the collation are ordered by their bytes so the order is always deterministic.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, PartialOrd, Hash, Serialize, Deserialize)]
+pub struct Collation {
+    /// Compares `A` and `a` as equal.
+    pub case_insensitive: bool,
+    /// Compares runs of digits by their numeric value, e.g. `foo-2` is ordered before `foo-10`.
+    pub natural: bool,
+    /// Compares `-` and `_` as equal, e.g. `serde-json` and `serde_json`.
+    pub dash_equals_underscore: bool,
+}
+
+impl Collation {
+    /// Compares the raw bytes of the strings.
+    pub fn byte() -> Self {
+        Self::default()
+    }
+
+    /// Compares the strings without regard to case.
+    pub fn case_insensitive() -> Self {
+        Self {
+            case_insensitive: true,
+            ..Self::default()
+        }
+    }
+
+    /// Compares the strings with digits compared by their numeric value.
+    pub fn natural() -> Self {
+        Self {
+            natural: true,
+            ..Self::default()
+        }
+    }
+
+    /// Compares two strings according to this collation.
+    pub fn compare(&self, str_1: &str, str_2: &str) -> Ordering {
+        let normalized_1 = self.normalize(str_1);
+        let normalized_2 = self.normalize(str_2);
+
+        let ordering = if self.natural {
+            natural_compare(&normalized_1, &normalized_2)
+        } else {
+            normalized_1.cmp(&normalized_2)
+        };
+
+        ordering.then_with(|| str_1.cmp(str_2))
+    }
+
+    fn normalize(&self, str: &str) -> String {
+        str.chars()
+            .flat_map(|c| {
+                let c = if self.dash_equals_underscore && c == '_' {
+                    '-'
+                } else {
+                    c
+                };
+
+                if self.case_insensitive {
+                    c.to_lowercase().collect::<Vec<char>>()
+                } else {
+                    vec![c]
+                }
+            })
+            .collect()
+    }
+}
+
+/// Compares the strings chunk by chunk where runs of digits are compared by their numeric value.
+fn natural_compare(str_1: &str, str_2: &str) -> Ordering {
+    let mut chars_1 = str_1.chars().peekable();
+    let mut chars_2 = str_2.chars().peekable();
+
+    loop {
+        match (chars_1.peek().copied(), chars_2.peek().copied()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(c1), Some(c2)) if c1.is_ascii_digit() && c2.is_ascii_digit() => {
+                let number_1 = take_number(&mut chars_1);
+                let number_2 = take_number(&mut chars_2);
+
+                // Numbers without leading zeros compare by length first, then by their digits.
+                let ordering = number_1
+                    .len()
+                    .cmp(&number_2.len())
+                    .then_with(|| number_1.cmp(&number_2));
+
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+            }
+            (Some(c1), Some(c2)) => {
+                if c1 != c2 {
+                    return c1.cmp(&c2);
+                }
+
+                chars_1.next();
+                chars_2.next();
+            }
+        }
+    }
+}
+
+/// Takes a run of digits from the iterator without its leading zeros.
+fn take_number(chars: &mut Peekable<Chars>) -> String {
+    let mut number = String::new();
+
+    while let Some(c) = chars.next_if(char::is_ascii_digit) {
+        if !(number.is_empty() && c == '0') {
+            number.push(c);
+        }
+    }
+
+    number
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cmp::Ordering;
+
+    use super::Collation;
+
+    #[test]
+    fn byte_collation() {
+        let collation = Collation::byte();
+
+        assert_eq!(collation.compare("B", "a"), Ordering::Less);
+        assert_eq!(collation.compare("foo-10", "foo-2"), Ordering::Less);
+    }
+
+    #[test]
+    fn case_insensitive_collation() {
+        let collation = Collation::case_insensitive();
+
+        assert_eq!(collation.compare("B", "a"), Ordering::Greater);
+        assert_eq!(collation.compare("A", "a"), Ordering::Less);
+    }
+
+    #[test]
+    fn natural_collation() {
+        let collation = Collation::natural();
+
+        assert_eq!(collation.compare("foo-2", "foo-10"), Ordering::Less);
+        assert_eq!(collation.compare("foo-02", "foo-10"), Ordering::Less);
+        assert_eq!(collation.compare("foo-10", "foo-10-a"), Ordering::Less);
+        assert_eq!(collation.compare("foo", "foo-1"), Ordering::Less);
+    }
+
+    #[test]
+    fn dash_equals_underscore_collation() {
+        let collation = Collation {
+            dash_equals_underscore: true,
+            ..Collation::default()
+        };
+
+        assert_eq!(collation.compare("a_b", "a-c"), Ordering::Less);
+        assert_eq!(collation.compare("a-b", "a_b"), Ordering::Less);
+    }
+}
@@ -0,0 +1,295 @@
+use std::collections::{HashMap, HashSet};
+
+use toml_edit::{Decor, Document, Item, Table};
+
+use crate::comments::{comment_lines, header_mut, header_paths, header_ref, HeaderPath};
+
+/// A comment that changes how the formatter treats the item it is placed on.
+///
+/// ```toml
+/// # toml-fmt: skip
+/// a = [ "left",   "untouched" ]
+///
+/// # toml-fmt: sort-off
+/// [dependencies]
+/// b = "0.1"
+/// a = "0.1"
+///
+/// # toml-fmt: off
+/// [features]
+/// c = [ "raw" ]
+/// # toml-fmt: on
+/// ```
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Directive {
+    /// `# toml-fmt: skip` leaves the key, or the section header and its keys, untouched.
+    Skip,
+    /// `# toml-fmt: off` leaves all following keys and section headers untouched.
+    Off,
+    /// `# toml-fmt: on` ends a region started by [Directive::Off].
+    On,
+    /// `# toml-fmt: sort-off` keeps the order of the keys of the following section.
+    SortOff,
+}
+
+impl Directive {
+    /// The prefixes of a directive comment, `# cargo-toml-fmt: skip` is accepted as well.
+    const PREFIXES: [&'static str; 2] = ["toml-fmt:", "cargo-toml-fmt:"];
+
+    /// Parses a comment line, e.g. `# toml-fmt: skip`.
+    pub fn parse(comment: &str) -> Option<Self> {
+        let comment = comment.trim().strip_prefix('#')?.trim();
+        let directive = Self::PREFIXES
+            .iter()
+            .find_map(|prefix| comment.strip_prefix(prefix))?;
+
+        match directive.trim() {
+            "skip" => Some(Self::Skip),
+            "off" => Some(Self::Off),
+            "on" => Some(Self::On),
+            "sort-off" => Some(Self::SortOff),
+            _ => None,
+        }
+    }
+
+    /// Returns the directives in the comments of the decor in the order they are written.
+    fn from_decor(decor: &Decor) -> Vec<Self> {
+        [decor.prefix(), decor.suffix()]
+            .into_iter()
+            .flatten()
+            .flat_map(comment_lines)
+            .filter_map(|comment| Self::parse(&comment))
+            .collect()
+    }
+}
+
+/// The directives of a document.
+///
+/// The directives are collected before formatting and handed to every rule, see [crate::TomlFormatter::set_directives].
+/// Rules that move or restructure sections leave the skipped sections in place,
+/// afterwards the skipped keys and the sort-off sections are restored as they were written.
+#[derive(Clone, Debug, Default)]
+pub struct Directives {
+    /// The section headers that are left untouched.
+    skipped_headers: HashSet<HeaderPath>,
+    /// The keys that are left untouched, by the path of their section.
+    skipped_keys: HashMap<HeaderPath, HashSet<String>>,
+    /// The sections whose keys keep their order.
+    sort_off: HashSet<HeaderPath>,
+}
+
+impl Directives {
+    /// Collects the directives in the comments of the document.
+    pub fn from_document(document: &Document) -> Self {
+        let mut directives = Self::default();
+
+        let mut is_off = false;
+
+        // The keys before the first section belong to the root of the document.
+        let paths = std::iter::once(vec![]).chain(header_paths(document));
+
+        for path in paths {
+            let table = match header_ref(document, &path) {
+                Some(table) => table,
+                None => continue,
+            };
+
+            // An `on` above the header ends the region before the section, an `off` starts it with the section.
+            let mut is_skipped = false;
+            if !path.is_empty() {
+                for directive in Directive::from_decor(table.decor()) {
+                    match directive {
+                        Directive::Skip => is_skipped = true,
+                        Directive::Off => is_off = true,
+                        Directive::On => is_off = false,
+                        Directive::SortOff => {
+                            directives.sort_off.insert(path.clone());
+                        }
+                    }
+                }
+            }
+            let skip_section = !path.is_empty() && (is_skipped || is_off);
+
+            if skip_section {
+                directives.skipped_headers.insert(path.clone());
+            }
+
+            for (key, item) in table.iter() {
+                if !Self::is_key(item) {
+                    continue;
+                }
+
+                let mut skip_key = skip_section;
+                let decors = table.key_decor(key).into_iter().chain(item_decor(item));
+
+                for directive in decors.flat_map(Directive::from_decor) {
+                    match directive {
+                        Directive::Skip => skip_key = true,
+                        Directive::Off => is_off = true,
+                        Directive::On => is_off = false,
+                        Directive::SortOff => {}
+                    }
+                }
+
+                if skip_key || is_off {
+                    // A raw region keeps the order of the keys as well.
+                    if is_off {
+                        directives.sort_off.insert(path.clone());
+                    }
+
+                    directives
+                        .skipped_keys
+                        .entry(path.clone())
+                        .or_default()
+                        .insert(key.to_string());
+                }
+            }
+        }
+
+        directives
+    }
+
+    /// Returns whether the document contains any directive.
+    pub fn is_empty(&self) -> bool {
+        self.skipped_headers.is_empty() && self.skipped_keys.is_empty() && self.sort_off.is_empty()
+    }
+
+    /// Returns whether the item is written as a key in its table, e.g. `a = 1` or `a.b = 1`, rather than as a section header.
+    ///
+    /// The directives of a key are read from its decor, the rules use the same check to find the keys of a table.
+    pub fn is_key(item: &Item) -> bool {
+        match item {
+            Item::Value(_) => true,
+            Item::Table(table) => table.is_dotted(),
+            _ => false,
+        }
+    }
+
+    /// Returns whether the section header at the path is left untouched.
+    pub fn skips_header(&self, path: &HeaderPath) -> bool {
+        self.skipped_headers.contains(path)
+    }
+
+    /// Returns whether the key of the section at the path is left untouched.
+    pub fn skips_key(&self, path: &HeaderPath, key: &str) -> bool {
+        self.skipped_keys
+            .get(path)
+            .is_some_and(|keys| keys.contains(key))
+    }
+
+    /// Restores the skipped items and the order of the sort-off sections of the formatted document,
+    /// the original document is the document as it was before formatting.
+    pub fn restore(&self, original: &Document, document: &mut Document) {
+        for path in &self.sort_off {
+            if let (Some(original), Some(table)) =
+                (header_ref(original, path), header_mut(document, path))
+            {
+                Self::restore_order(original, table);
+            }
+        }
+
+        for path in &self.skipped_headers {
+            let original_table = match header_ref(original, path) {
+                Some(original_table) => original_table,
+                None => continue,
+            };
+
+            match header_mut(document, path) {
+                Some(table) => *table.decor_mut() = original_table.decor().clone(),
+                // A rule turned the section into a value, the section is put back as it was written.
+                None => {
+                    let parent_path = path[..path.len() - 1].to_vec();
+                    let original_key = header_ref(original, &parent_path)
+                        .zip(path.last())
+                        .and_then(|(parent, (key, _))| parent.get_key_value(key))
+                        .map(|(key, _)| key);
+
+                    if let (Some(original_key), Some(parent)) =
+                        (original_key, header_mut(document, &parent_path))
+                    {
+                        parent.insert_formatted(original_key, Item::Table(original_table.clone()));
+                    }
+                }
+            }
+
+            // The key of the header, e.g. `[ "a" ]`, is stored in the parent table.
+            if let (Some((key, _)), Some(parent)) = (
+                path.last(),
+                header_mut(document, &path[..path.len() - 1].to_vec()),
+            ) {
+                if let (Some(original), Some(decor)) = (
+                    header_ref(original, &path[..path.len() - 1].to_vec())
+                        .and_then(|parent| parent.key_decor(key)),
+                    parent.key_decor_mut(key),
+                ) {
+                    *decor = original.clone();
+                }
+            }
+        }
+
+        for (path, keys) in &self.skipped_keys {
+            if let (Some(original), Some(table)) =
+                (header_ref(original, path), header_mut(document, path))
+            {
+                for key in keys {
+                    if let Some((original_key, original_item)) = original.get_key_value(key) {
+                        // Replaces the key and its value in place, or appends the key when a rule removed it.
+                        table.insert_formatted(original_key, original_item.clone());
+                    }
+                }
+
+                Self::restore_positions(original, table, keys);
+            }
+        }
+    }
+
+    /// Moves the skipped keys back to their original position, the other keys keep the order given by the rules.
+    fn restore_positions(original: &Table, table: &mut Table, skipped: &HashSet<String>) {
+        let mut pinned = original
+            .iter()
+            .enumerate()
+            .filter(|(_, (key, _))| skipped.contains(*key))
+            .map(|(idx, (key, _))| (idx, key.to_string()))
+            .collect::<Vec<(usize, String)>>();
+        pinned.sort();
+
+        let mut order = table
+            .iter()
+            .map(|(key, _)| key.to_string())
+            .filter(|key| !skipped.contains(key))
+            .collect::<Vec<String>>();
+
+        for (idx, key) in pinned {
+            order.insert(idx.min(order.len()), key);
+        }
+
+        let order = order
+            .into_iter()
+            .enumerate()
+            .map(|(idx, key)| (key, idx))
+            .collect::<HashMap<String, usize>>();
+        let order_of = |key: &str| order.get(key).copied().unwrap_or(usize::MAX);
+
+        table
+            .sort_values_by(|key_1, _, key_2, _| order_of(key_1.get()).cmp(&order_of(key_2.get())));
+    }
+
+    /// Orders the keys of the table as they were ordered in the original table, new keys are placed last.
+    fn restore_order(original: &Table, table: &mut Table) {
+        let original_order = original
+            .iter()
+            .enumerate()
+            .map(|(idx, (key, _))| (key.to_string(), idx))
+            .collect::<HashMap<String, usize>>();
+
+        let order_of = |key: &str| original_order.get(key).copied().unwrap_or(usize::MAX);
+
+        table
+            .sort_values_by(|key_1, _, key_2, _| order_of(key_1.get()).cmp(&order_of(key_2.get())));
+    }
+}
+
+/// Returns the decor of a value, which holds the comment on the same line as the key.
+fn item_decor(item: &Item) -> Option<&Decor> {
+    item.as_value().map(|value| value.decor())
+}
@@ -1,6 +1,24 @@
-use toml_edit::{Array, Decor, Document, Item, Key, KeyMut, Table, TableLike, Value};
-
-use crate::{iter_sections_as_tables, toml_config::TomlFormatConfig};
+use strum::VariantNames;
+use toml_edit::{value, Array, Decor, Document, InlineTable, Item, Key, KeyMut, Table, Value};
+
+use crate::{
+    comments::{
+        comment_lines, has_comments, header_mut, header_paths, set_blank_lines, trim_header_prefix,
+        trim_prefix, trim_prefix_lines, trim_suffix, ArrayComments, Comments, HeaderPath,
+    },
+    directives::Directives,
+    iter_dependency_tables_mut, iter_sections_as_tables, iter_values_mut,
+    package_order::DependencySection,
+    toml_config::{
+        ArrayPacking, BlankLines, DependencyStyle, DottedKeyStyle, QuoteStyle, TomlFormatConfig,
+    },
+    utils::{escape_basic_string, fill_array},
+    version::VersionRequirement,
+    width::{
+        column_after, display_width, key_mut_text, key_text, key_value_width, value_text, width_at,
+        INLINE_KEY_DECOR, KEY_DECOR, TRAILING_VALUE_DECOR, VALUE_DECOR,
+    },
+};
 
 use super::TomlFormatter;
 
@@ -22,117 +40,176 @@ impl TomlFormatter for SectionKeyNameTrimmer {
     }
 }
 
+/// Trims the blank lines and indentation around the keys of the sections.
+///
+/// Comments are kept, each on their own line, e.g. `    # comment\n\n    a = 1` becomes `# comment\na = 1`.
 pub struct KeyTrimmer;
 
 impl TomlFormatter for KeyTrimmer {
     fn visit_document(
         &mut self,
         toml_document: &mut Document,
-        _config: &TomlFormatConfig,
+        config: &TomlFormatConfig,
     ) -> anyhow::Result<()> {
+        let keep_blank_lines = config.blank_lines.is_some();
+
         iter_sections_as_tables(toml_document, |_, section| {
-            trim_decor_blank_lines(section.decor_mut());
+            let decor = section.decor_mut();
+            let prefix = decor.prefix().unwrap_or_default();
+            decor.set_prefix(match keep_blank_lines {
+                true => trim_prefix_lines(prefix),
+                false => trim_header_prefix(prefix),
+            });
+            decor.set_suffix(trim_suffix(decor.suffix().unwrap_or_default()));
 
             section.iter_mut().for_each(|(mut key, _)| {
-                trim_decor_blank_lines(key.decor_mut());
+                let prefix = key.decor().prefix().unwrap_or_default();
+                let prefix = match keep_blank_lines {
+                    true => trim_prefix_lines(prefix),
+                    false => trim_prefix(prefix),
+                };
+                key.decor_mut().set_prefix(prefix);
             });
         });
         Ok(())
     }
 }
 
-pub struct KeyQuoteTrimmer;
+/// Removes the quotes from keys when they are not required, e.g. `"a" = 1` becomes `a = 1`.
+///
+/// Keys that can't be written bare are quoted with the configured [QuoteStyle], e.g. `'cfg(unix)'`.
+/// Only the representation of the key is changed, the comments around the key are kept.
+/// A key that can't be requoted without changing its name is left as it is and a warning is reported.
+#[derive(Default)]
+pub struct KeyQuoteTrimmer {
+    warnings: Vec<String>,
+}
 
 impl TomlFormatter for KeyQuoteTrimmer {
     fn visit_document(
         &mut self,
         toml_document: &mut Document,
-        _config: &TomlFormatConfig,
+        config: &TomlFormatConfig,
     ) -> anyhow::Result<()> {
-        iter_sections_as_tables(toml_document, |_, section| {
-            Self::visit_table(section);
-        });
+        self.visit_table(toml_document.as_table_mut(), config.key_quote_style);
+
         Ok(())
     }
+
+    fn take_warnings(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.warnings)
+    }
 }
 
 impl KeyQuoteTrimmer {
-    fn visit_table(table: &mut Table) {
-        let mut trimmed_keys = vec![];
-
-        // Iterate table keys and trim away quotes.
-        table.iter_mut().for_each(|(key, item)| {
-            let trimmed_key = Self::remove_quotes(&key);
-
-            trimmed_keys.push((trimmed_key, item.clone()));
-        });
-
-        // Iterate trimmed keys and remove them and then insert them back into the table.
-        trimmed_keys
-            .into_iter()
-            .for_each(|(trimmed_key, mut item)| {
-                Self::visit_item(&mut item);
-
-                table.remove(trimmed_key.get());
-                table.insert(trimmed_key.get(), item);
+    // Recursively iterate items and trim quotes from key names 'e.g' "key" = value -> key = value.
+    fn visit_table(&mut self, table: &mut Table, quote_style: QuoteStyle) {
+        let keys = table
+            .iter()
+            .map(|(key, _)| key.to_string())
+            .collect::<Vec<String>>();
+
+        for key in keys {
+            let requoted = match table.get_key_value(&key) {
+                Some((key, _)) => self.requote_or_warn(key, quote_style),
+                None => continue,
+            };
+
+            // Replaces the key in place, so the order of the keys is kept.
+            if let Some(requoted) = requoted {
+                let item = std::mem::take(&mut table[&key]);
+                table.insert_formatted(&requoted, item);
+            }
 
-                // Unfortunately I can't figure out how to internally update the string representation of the key,
-                // hence we have to do it this way.
-                table.iter_mut().for_each(|(mut key, _)| {
-                    Self::trim_key(&mut key, &trimmed_key);
-                });
-            });
+            match &mut table[&key] {
+                Item::Value(value) => self.visit_value(value, quote_style),
+                Item::Table(table) => self.visit_table(table, quote_style),
+                Item::ArrayOfTables(tables) => {
+                    for table in tables.iter_mut() {
+                        self.visit_table(table, quote_style);
+                    }
+                }
+                Item::None => {}
+            }
+        }
     }
 
-    // Recursively iterate items and trim quotes from key names 'e.g' "key" = value -> key = value.
-    fn visit_item(item: &mut Item) {
-        match item {
-            Item::Value(value) => {
-                Self::visit_value(value);
+    fn visit_value(&mut self, value: &mut Value, quote_style: QuoteStyle) {
+        match value {
+            Value::InlineTable(inline_table) => {
+                let keys = inline_table
+                    .iter()
+                    .map(|(key, _)| key.to_string())
+                    .collect::<Vec<String>>();
+
+                for key in keys {
+                    let requoted = match inline_table.get_key_value(&key) {
+                        Some((key, _)) => self.requote_or_warn(key, quote_style),
+                        None => continue,
+                    };
+
+                    if let (Some(requoted), Some(value)) = (requoted, inline_table.get(&key)) {
+                        inline_table.insert_formatted(&requoted, value.clone());
+                    }
+
+                    if let Some(value) = inline_table.get_mut(&key) {
+                        self.visit_value(value, quote_style);
+                    }
+                }
             }
-            Item::Table(table) => Self::visit_table(table),
-            Item::ArrayOfTables(tables) => {
-                tables.iter_mut().for_each(|table| Self::visit_table(table))
+            Value::Array(array) => {
+                for value in array.iter_mut() {
+                    self.visit_value(value, quote_style);
+                }
             }
-            Item::None => {}
-        };
+            _ => {}
+        }
     }
 
-    fn visit_value(value: &mut Value) {
-        if let Value::InlineTable(inline_table) = value {
-            let mut trimmed_key_value = None;
-            inline_table.iter_mut().for_each(|(key, inline_value)| {
-                let trimmed_key = Self::remove_quotes(&key);
+    /// Returns the requoted key, see [KeyQuoteTrimmer::requote], a key that fails to requote is kept and reported.
+    fn requote_or_warn(&mut self, key: &Key, quote_style: QuoteStyle) -> Option<Key> {
+        match Self::requote(key, quote_style) {
+            Ok(requoted) => requoted,
+            Err(e) => {
+                self.warnings
+                    .push(format!("{e} The key `{}` is left as it is.", key.get()));
+                None
+            }
+        }
+    }
 
-                trimmed_key_value = Some((trimmed_key, inline_value.clone()));
-            });
+    /// Returns the key written bare when it is a valid bare key and otherwise quoted with the quote style,
+    /// or `None` when the key is already written that way.
+    fn requote(key: &Key, quote_style: QuoteStyle) -> anyhow::Result<Option<Key>> {
+        let raw = key.to_repr().as_raw().to_string();
+        let quoted = if Self::is_bare_key(key.get()) {
+            key.get().to_string()
+        } else {
+            StringQuoteFormatting::quote(key.get(), quote_style)
+        };
 
-            if let Some((trimmed_key, ref mut value)) = trimmed_key_value {
-                Self::visit_value(value);
+        if quoted == raw {
+            return Ok(None);
+        }
 
-                inline_table.remove(trimmed_key.get());
-                inline_table.insert(trimmed_key.get(), value.clone());
+        let requoted = quoted
+            .parse::<Key>()
+            .map_err(|e| anyhow::anyhow!("Failed to quote key. {e}"))?;
 
-                inline_table.iter_mut().for_each(|(mut key, _)| {
-                    Self::trim_key(&mut key, &trimmed_key);
-                });
-            }
+        // Never change the key itself.
+        if requoted.get() != key.get() {
+            anyhow::bail!("Changing the quotes of a key changed its name.");
         }
-    }
 
-    fn trim_key(original_key: &mut KeyMut, trimmed_key: &Key) {
-        original_key
-            .decor_mut()
-            .set_prefix(trimmed_key.decor().prefix().unwrap_or("").to_string());
-        original_key
-            .decor_mut()
-            .set_suffix(trimmed_key.decor().suffix().unwrap_or("").to_string());
+        Ok(Some(requoted.with_decor(key.decor().clone())))
     }
 
-    fn remove_quotes(key: &KeyMut) -> Key {
-        let raw_key = key.to_repr().as_raw().to_string();
-        let trimmed = raw_key.trim_matches('"');
-        Key::new(trimmed.to_string()).with_decor(key.decor().clone())
+    /// A bare key may only contain ASCII letters, ASCII digits, underscores and dashes, and can't be empty.
+    fn is_bare_key(key: &str) -> bool {
+        !key.is_empty()
+            && key
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
     }
 }
 
@@ -145,7 +222,7 @@ fn trim_decor_blank_lines(decor: &mut Decor) {
 
 /// trim blank lines at the beginning and end
 fn trim_blank_lines(s: &str) -> String {
-    return format!("{}", s.trim());
+    s.trim().to_string()
 }
 
 /// Formats tables and inline tables.
@@ -160,21 +237,25 @@ impl TomlFormatter for TableFormatting {
     fn visit_document(
         &mut self,
         toml_document: &mut Document,
-        _config: &TomlFormatConfig,
+        config: &TomlFormatConfig,
     ) -> anyhow::Result<()> {
+        let keep_blank_lines = config.blank_lines.is_some();
+
         iter_sections_as_tables(toml_document, |section_key, section| {
             // Remove spaces from section key [ section ] -> [section]
             section_key.fmt();
 
-            section.decor_mut().set_suffix("");
-
             // Recursively iterate table key values and format them.
-            self.fmt_table(section, 0);
-
-            let prefix = Self::fmt_prefix_and_preserve_comments(
-                section.decor().prefix().unwrap_or_default(),
-            );
+            self.fmt_table(section, 0, keep_blank_lines);
+
+            let prefix = section.decor().prefix().unwrap_or_default();
+            let prefix = match keep_blank_lines {
+                true => trim_prefix_lines(prefix),
+                false => trim_header_prefix(prefix),
+            };
+            let suffix = trim_suffix(section.decor().suffix().unwrap_or_default());
             section.decor_mut().set_prefix(prefix);
+            section.decor_mut().set_suffix(suffix);
         });
         Ok(())
     }
@@ -182,9 +263,12 @@ impl TomlFormatter for TableFormatting {
 
 impl TableFormatting {
     /// Visit the item and format its contained type.
-    fn visit_item(&self, key: &mut KeyMut, item: &mut Item, depth: usize) {
-        let trimmed_prefix =
-            Self::fmt_prefix_and_preserve_comments(key.decor_mut().prefix().unwrap());
+    fn visit_item(&self, key: &mut KeyMut, item: &mut Item, depth: usize, keep_blank_lines: bool) {
+        let prefix = key.decor().prefix().unwrap_or_default();
+        let trimmed_prefix = match keep_blank_lines {
+            true => trim_prefix_lines(prefix),
+            false => trim_prefix(prefix),
+        };
         key.decor_mut().set_prefix(trimmed_prefix);
 
         match item {
@@ -193,12 +277,12 @@ impl TableFormatting {
                 key.decor_mut().set_suffix(" ");
             }
             Item::Table(table) => {
-                self.fmt_table(table, 0);
+                self.fmt_table(table, 0, keep_blank_lines);
                 key.decor_mut().set_suffix("");
             }
             Item::ArrayOfTables(tables) => {
                 for table in tables.iter_mut() {
-                    self.fmt_table(table, depth);
+                    self.fmt_table(table, depth, keep_blank_lines);
                     key.decor_mut().set_suffix("");
                 }
             }
@@ -208,8 +292,8 @@ impl TableFormatting {
 
     /// Iterate the value and recursively format its contained types.
     fn fmt_value(&self, value: &mut Value) {
-        // Fetch comment if there is anyone after the value e.g ("key" = "value" # comment) will return '# comment'.
-        let (prefix_comment, suffix_comment) = Self::get_comments(value.decor());
+        // The comment after the value e.g ("key" = "value" # comment), if there is one.
+        let comments = Comments::from_decor(value.decor());
 
         match value {
             Value::Array(array) => {
@@ -224,138 +308,390 @@ impl TableFormatting {
                 // Format all key and value pairs. This strips unnecessarily whitespace and adds spaces between key and value.
                 // e.g '{key=   value}' -> '{ key = value }'
                 inline_table.fmt();
-
-                // Remove prefix and postfix white spaces from the inline table.
-                inline_table.decor_mut().set_prefix(" ");
-                inline_table.decor_mut().set_suffix("");
             }
             Value::Float(..)
             | Value::String(..)
             | Value::Datetime(..)
             | Value::Integer(..)
-            | Value::Boolean(_) => {
-                // Remove prefix and postfix white spaces from the inline table.
-                // e.g 'a =    true    ' -> 'a = true'
-                value.decor_mut().set_prefix(" ");
-                value.decor_mut().set_suffix("");
-            }
+            | Value::Boolean(_) => {}
         }
 
-        // Adds back the comment that was trimmed during formatting.
-        Self::set_comment(prefix_comment, suffix_comment, value.decor_mut());
+        // Remove prefix and postfix white spaces from the value and add back its comment.
+        // e.g 'a =    true    # comment' -> 'a = true # comment'
+        value.decor_mut().set_prefix(" ");
+        value.decor_mut().set_suffix(comments.suffix());
     }
 
     // Iterate array of `Values` and format them.
     fn fmt_array(&self, array: &mut Array) {
-        let (prefix_comment, suffix_comment) = Self::get_comments(array.decor_mut());
+        let comments = ArrayComments::from_array(array);
 
         for element in array.iter_mut() {
             self.fmt_value(element);
         }
 
-        array.fmt();
-
-        array.decor_mut().set_prefix(" ");
-        array.decor_mut().set_suffix("");
-
-        Self::set_comment(prefix_comment, suffix_comment, array.decor_mut());
+        // Comments within the array are kept by writing each value on its own line.
+        if comments.is_empty() {
+            array.fmt();
+        } else {
+            comments.wrap_array(array, "    ", "", false);
+        }
     }
 
     // Iterate table key values and recursively format them.
-    fn fmt_table(&self, table: &mut Table, depth: usize) {
+    fn fmt_table(&self, table: &mut Table, depth: usize, keep_blank_lines: bool) {
         for (ref mut key, ref mut val) in table.iter_mut() {
-            self.visit_item(key, val, depth + 1);
+            self.visit_item(key, val, depth + 1, keep_blank_lines);
         }
     }
+}
 
-    fn fmt_prefix_and_preserve_comments(prefix: &str) -> String {
-        let trimmed = prefix
-            .lines()
-            .into_iter()
-            .filter_map(|line| {
-                let trimmed = line.trim();
-                if trimmed.is_empty() {
-                    None
-                } else {
-                    Some(format!("{trimmed}"))
-                }
-            })
-            .collect::<Vec<String>>()
-            .join("\n");
+/// Appends a line after the last item in a table at the end of each section.
+/// Sections are to be separated by one line.
+pub struct AppendLineAfterSection;
 
-        if trimmed.is_empty() {
-            "".to_string()
-        } else {
-            format!("{}\n", trimmed)
-        }
+impl TomlFormatter for AppendLineAfterSection {
+    fn visit_document(
+        &mut self,
+        toml_document: &mut Document,
+        _config: &TomlFormatConfig,
+    ) -> anyhow::Result<()> {
+        iter_sections_as_tables(toml_document, |section_key, section| {
+            // Package section is the first section in the file hence we dont want to prepend it with a new line.
+            if section_key.get() != "package" {
+                Self::append_empty_line(section);
+            }
+        });
+        Ok(())
     }
+}
+
+impl AppendLineAfterSection {
+    fn append_empty_line(table: &mut Table) {
+        let decor = table.decor_mut();
+        let prefix = decor.prefix().unwrap_or("").to_owned();
+        decor.set_prefix("\n".to_owned() + &prefix);
+    }
+}
+
+/// Sets the blank lines between keys, comments and sections as configured by [TomlFormatConfig::blank_lines].
+///
+/// ```toml
+/// [package]
+///
+/// name = "a"
+///
+///
+/// version = "0.1.0"
+/// [dependencies]
+/// ```
+///
+/// to
+///
+/// ```toml
+/// [package]
+/// name = "a"
+///
+/// version = "0.1.0"
+///
+/// [dependencies]
+/// ```
+///
+/// The lines of a dotted table, e.g. `a.b = 1` and `a.c = 2`, share the prefix of the dotted key `a`,
+/// toml_edit writes it before each line. The keys within the dotted table, written after the `.`, get no blank lines.
+pub struct BlankLineFormatting;
 
-    fn get_comments(decor: &Decor) -> (String, String) {
-        let prefix_comment = Self::get_comment(decor.prefix().unwrap_or_default());
-        let suffix_comment = Self::get_comment(decor.suffix().unwrap_or_default());
+impl TomlFormatter for BlankLineFormatting {
+    fn visit_document(
+        &mut self,
+        toml_document: &mut Document,
+        config: &TomlFormatConfig,
+    ) -> anyhow::Result<()> {
+        let policy = config.blank_lines.unwrap();
+
+        // The keys before the first section are at the top of the file, as is the first section when there are none.
+        let mut is_first = true;
+        Self::visit_keys(toml_document.as_table_mut(), &mut is_first, &policy);
+
+        for path in header_paths(toml_document) {
+            if let Some(table) = header_mut(toml_document, &path) {
+                let leading = if is_first { 0 } else { policy.between_sections };
+                let prefix = table.decor().prefix().unwrap_or_default();
+                let prefix = set_blank_lines(prefix, Some(leading), true, &policy);
+                table.decor_mut().set_prefix(prefix);
+
+                // There are no blank lines after a section header.
+                is_first = true;
+                Self::visit_keys(table, &mut is_first, &policy);
+                is_first = false;
+            }
+        }
 
-        (prefix_comment, suffix_comment)
+        Ok(())
     }
+}
+
+impl BlankLineFormatting {
+    /// Sets the blank lines before the keys of the table, the first key has none when `is_first` is set.
+    fn visit_keys(table: &mut Table, is_first: &mut bool, policy: &BlankLines) {
+        for (mut key, item) in table.iter_mut() {
+            if Directives::is_key(item) {
+                let leading = is_first.then_some(0);
+                let prefix = key.decor().prefix().unwrap_or_default();
+                let prefix = set_blank_lines(prefix, leading, false, policy);
+                key.decor_mut().set_prefix(prefix);
+
+                *is_first = false;
+            }
 
-    fn set_comment(prefix_comment: String, suffix_comment: String, decor_mut: &mut Decor) {
-        if !suffix_comment.is_empty() {
-            Self::set_comment_suffix(&suffix_comment, decor_mut);
+            if let Item::Table(table) = item {
+                if table.is_dotted() {
+                    Self::visit_dotted_keys(table, policy);
+                }
+            }
         }
+    }
 
-        if !prefix_comment.is_empty() {
-            Self::set_comment_prefix(&prefix_comment, decor_mut);
+    /// Removes the blank lines before the keys of a dotted table, which would be written within the dotted key, e.g. `a.\n\nb = 1`.
+    fn visit_dotted_keys(table: &mut Table, policy: &BlankLines) {
+        for (mut key, item) in table.iter_mut() {
+            let prefix = key.decor().prefix().unwrap_or_default();
+            let prefix = set_blank_lines(prefix, Some(0), false, policy);
+            key.decor_mut().set_prefix(prefix);
+
+            if let Item::Table(table) = item {
+                if table.is_dotted() {
+                    Self::visit_dotted_keys(table, policy);
+                }
+            }
         }
     }
+}
+
+/// Aligns the `=` and the trailing comments of the keys within each group of keys, as configured by
+/// [TomlFormatConfig::align_keys] and [TomlFormatConfig::align_comments].
+///
+/// ```toml
+/// [dependencies]
+/// a = "0.1" # The a crate.
+/// serde = { version = "1.0", features = ["derive"] } # Serialization.
+///
+/// tokio = "1"
+/// ```
+///
+/// to
+///
+/// ```toml
+/// [dependencies]
+/// a     = "0.1"                                        # The a crate.
+/// serde = { version = "1.0", features = ["derive"] } # Serialization.
+///
+/// tokio = "1"
+/// ```
+///
+/// The alignment is computed from scratch each time, so adding a longer key re-aligns its group.
+pub struct AlignEntries;
+
+impl TomlFormatter for AlignEntries {
+    fn visit_document(
+        &mut self,
+        toml_document: &mut Document,
+        config: &TomlFormatConfig,
+    ) -> anyhow::Result<()> {
+        Self::visit_table(toml_document.as_table_mut(), config);
 
-    /// Strips the suffix from an item preserving comments.
-    fn set_comment_suffix(suffix: &str, decor_mut: &mut Decor) {
-        decor_mut.set_suffix(format!(" #{}", suffix));
+        Ok(())
     }
+}
 
-    /// Strips the suffix from an item preserving comments.
-    fn set_comment_prefix(suffix: &str, decor_mut: &mut Decor) {
-        decor_mut.set_prefix(format!(" #{}", suffix));
+impl AlignEntries {
+    fn visit_table(table: &mut Table, config: &TomlFormatConfig) {
+        Self::align(table, config);
+
+        for (_, item) in table.iter_mut() {
+            match item {
+                Item::Table(table) if !table.is_dotted() => Self::visit_table(table, config),
+                Item::ArrayOfTables(tables) => tables
+                    .iter_mut()
+                    .for_each(|table| Self::visit_table(table, config)),
+                _ => {}
+            }
+        }
     }
 
-    /// Returns the comment. if present, fetched by splitting the string at #.
-    fn get_comment(input: &str) -> String {
-        let split = input.split("#").collect::<Vec<&str>>();
+    /// Aligns the keys with a value of the table, dotted keys are left as they are.
+    fn align(table: &mut Table, config: &TomlFormatConfig) {
+        let max_width = config.wrap_array.unwrap_or(usize::MAX);
+
+        // The group, key width and value text without its trailing comment of each key.
+        let mut group = 0;
+        let mut entries = vec![];
+        for (key, item) in table.iter() {
+            let value = match item.as_value() {
+                Some(value) => value,
+                None => continue,
+            };
+
+            let prefix = table
+                .key_decor(key)
+                .and_then(Decor::prefix)
+                .unwrap_or_default();
+            let lines = prefix.split('\n').collect::<Vec<&str>>();
+            if lines[..lines.len() - 1]
+                .iter()
+                .any(|line| line.trim().is_empty())
+            {
+                group += 1;
+            }
+
+            // The indentation of the key, e.g. within an indented `[[bin]]` entry, counts towards the width of the line.
+            let indent = lines[lines.len() - 1];
+
+            let (key, _) = table.get_key_value(key).unwrap();
+            let mut code = value.clone();
+            code.decor_mut().set_suffix("");
+
+            entries.push((
+                group,
+                display_width(indent) + display_width(key.to_repr().as_raw()),
+                value_text(&code, VALUE_DECOR),
+                Comments::from_decor(value.decor()).trailing,
+            ));
+        }
+
+        // The padding after each key and the spaces before each trailing comment, the key widths include their indentation.
+        let mut key_padding = vec![1; entries.len()];
+        let mut comment_padding = vec![1; entries.len()];
+
+        for group in 0..=group {
+            let members = (0..entries.len())
+                .filter(|idx| entries[*idx].0 == group)
+                .collect::<Vec<usize>>();
+
+            if config.align_keys {
+                let key_width = members.iter().map(|idx| entries[*idx].1).max().unwrap_or(0);
+
+                for idx in &members {
+                    let (_, width, code, _) = &entries[*idx];
+                    if width_at(key_width + 2, code) <= max_width {
+                        key_padding[*idx] = key_width - width + 1;
+                    }
+                }
+            }
+
+            // Multi-line values, e.g. wrapped arrays, end on a different line than the key.
+            let code_width = |idx: usize| {
+                let (_, width, code, _) = &entries[idx];
+                (!code.contains('\n')).then(|| width + key_padding[idx] + 1 + display_width(code))
+            };
+
+            if config.align_comments {
+                let column = members
+                    .iter()
+                    .filter_map(|idx| code_width(*idx))
+                    .max()
+                    .unwrap_or(0);
+
+                for idx in &members {
+                    if let (Some(width), Some(comment)) = (code_width(*idx), &entries[*idx].3) {
+                        if column + 1 + display_width(comment) <= max_width {
+                            comment_padding[*idx] = column - width + 1;
+                        }
+                    }
+                }
+            }
+        }
+
+        let values = table
+            .iter_mut()
+            .filter(|(_, item)| item.is_value())
+            .zip(key_padding.into_iter().zip(comment_padding));
 
-        split
-            .get(1)
-            .map_or("".to_string(), |s| s.trim_end().to_string())
+        for ((mut key, item), (key_padding, comment_padding)) in values {
+            if config.align_keys {
+                key.decor_mut().set_suffix(" ".repeat(key_padding));
+            }
+
+            let value = item.as_value_mut().unwrap();
+            if let Some(comment) = Comments::from_decor(value.decor()).trailing {
+                if config.align_comments {
+                    value
+                        .decor_mut()
+                        .set_suffix(format!("{}{comment}", " ".repeat(comment_padding)));
+                }
+            }
+        }
     }
 }
 
-/// Appends a line after the last item in a table at the end of each section.
-/// Sections are to be separated by one line.
-pub struct AppendLineAfterSection;
+/// Indents the keys of sub-tables and arrays of tables as configured by [TomlFormatConfig::table_indent].
+///
+/// ```toml
+/// [dependencies.a]
+/// # The version.
+/// version = "0.1"
+/// ```
+///
+/// to
+///
+/// ```toml
+/// [dependencies.a]
+///     # The version.
+///     version = "0.1"
+/// ```
+pub struct TableIndentation;
 
-impl TomlFormatter for AppendLineAfterSection {
+impl TomlFormatter for TableIndentation {
     fn visit_document(
         &mut self,
         toml_document: &mut Document,
-        _config: &TomlFormatConfig,
+        config: &TomlFormatConfig,
     ) -> anyhow::Result<()> {
-        iter_sections_as_tables(toml_document, |section_key, section| {
-            // Package section is the first section in the file hence we dont want to prepend it with a new line.
-            if section_key.get() != "package" {
-                Self::append_empty_line(section);
+        let indent = config.table_indent.unwrap().as_string();
+
+        for path in header_paths(toml_document) {
+            // Only the keys of `[a.b]` and `[[a]]` are indented, not those of `[a]`.
+            if path.len() < 2 && path.iter().all(|(_, idx)| idx.is_none()) {
+                continue;
             }
-        });
+
+            if let Some(table) = header_mut(toml_document, &path) {
+                for (mut key, item) in table.iter_mut() {
+                    if Directives::is_key(item) {
+                        let prefix =
+                            Self::indent(key.decor().prefix().unwrap_or_default(), &indent);
+                        key.decor_mut().set_prefix(prefix);
+                    }
+                }
+            }
+        }
+
         Ok(())
     }
 }
 
-impl AppendLineAfterSection {
-    fn append_empty_line(table: &mut Table) {
-        let decor = table.decor_mut();
-        let prefix = decor.prefix().unwrap_or("").to_owned();
-        decor.set_prefix("\n".to_owned() + &prefix);
+impl TableIndentation {
+    /// Indents the comment lines of the prefix and the key itself, blank lines are left empty.
+    fn indent(prefix: &str, indent: &str) -> String {
+        let lines = prefix.split('\n').collect::<Vec<&str>>();
+
+        let mut indented = lines[..lines.len() - 1]
+            .iter()
+            .map(|line| match line.trim() {
+                "" => "\n".to_string(),
+                line => format!("{indent}{line}\n"),
+            })
+            .collect::<String>();
+
+        indented.push_str(indent);
+        indented
     }
 }
 
 /// Wraps an array when it surpasses a configurable line length.
+///
+/// Arrays within inline tables, arrays, sub-tables and arrays of tables are wrapped as well,
+/// nested arrays are indented one level deeper than the array they are in.
 pub struct WrapArray;
 
 impl TomlFormatter for WrapArray {
@@ -365,8 +701,12 @@ impl TomlFormatter for WrapArray {
         config: &TomlFormatConfig,
     ) -> anyhow::Result<()> {
         iter_sections_as_tables(toml_document, |section_key, section| {
-            if section_key.get() != "package" {
-                self.visit_table(section, config.wrap_array.unwrap())
+            if !config
+                .exclude_tables_from_wrapping
+                .iter()
+                .any(|table| table == section_key.get())
+            {
+                self.visit_table(section, 0, config)
             }
         });
 
@@ -375,35 +715,121 @@ impl TomlFormatter for WrapArray {
 }
 
 impl WrapArray {
-    fn visit_table(&self, table: &mut Table, wrap_array: usize) {
-        for (key, value) in table.iter_mut() {
-            // Only deal with array values.
-            if let Item::Value(Value::Array(array)) = value {
-                self.format_array(key, array, wrap_array);
+    /// Wraps the arrays within the table, `offset` is the width of the parents of a dotted table, e.g. `a.` of `a.b = []`.
+    fn visit_table(&self, table: &mut Table, offset: usize, config: &TomlFormatConfig) {
+        for (key, item) in table.iter_mut() {
+            match item {
+                Item::Value(value) => {
+                    // The indentation of the key, e.g. within an indented `[[bin]]` entry.
+                    let prefix = key.decor().prefix().unwrap_or_default();
+                    let line_indent = prefix.rsplit('\n').next().unwrap_or_default().to_string();
+
+                    let offset = column_after(offset, &key_mut_text(&key, KEY_DECOR)) + 1;
+                    self.visit_value(value, offset, VALUE_DECOR, &line_indent, config)
+                }
+                Item::Table(table) if table.is_dotted() => {
+                    let offset = column_after(offset, &key_mut_text(&key, ("", ""))) + 1;
+                    self.visit_table(table, offset, config)
+                }
+                Item::Table(table) => self.visit_table(table, 0, config),
+                Item::ArrayOfTables(tables) => tables
+                    .iter_mut()
+                    .for_each(|table| self.visit_table(table, 0, config)),
+                Item::None => {}
             }
         }
     }
 
-    /// Formats an array by wrapping it when it surpasses a configurable line length.
-    fn format_array(&self, key: KeyMut<'_>, array: &mut Array, wrap_array: usize) {
-        // Format to [item1, item2, ...]
-        array.fmt();
+    /// Wraps the arrays within the value, `offset` is the width of the line before the value,
+    /// `default_decor` the decor written around the value when it has none and `line_indent` the indentation of the line.
+    fn visit_value(
+        &self,
+        value: &mut Value,
+        offset: usize,
+        default_decor: (&str, &str),
+        line_indent: &str,
+        config: &TomlFormatConfig,
+    ) {
+        let max_width = config.wrap_array.unwrap();
 
-        // Length of key doesn't include decor. Length of array does. So we add 2 (" =").
-        if key.get().len() + 1 + array.to_string().chars().count() > wrap_array {
-            array.iter_mut().for_each(|item| {
-                item.decor_mut().set_prefix("\n    ");
-            });
+        match value {
+            Value::Array(array) => {
+                // Format to [item1, item2, ...]
+                let comments = ArrayComments::from_array(array);
+                if comments.is_empty() {
+                    array.fmt();
+                }
+
+                let fits = width_at(offset, &value_text(value, default_decor)) <= max_width;
+                let array = value.as_array_mut().unwrap();
 
-            array
-                .iter_mut()
-                .last()
-                .map(|l| l.decor_mut().set_suffix("\n"));
+                // An array with comments can only be written with each value on its own line.
+                if fits && comments.is_empty() {
+                    return;
+                }
+
+                self.wrap_array(array, &comments, line_indent, config);
+
+                // The values of a wrapped array are each on a line of their own, one level deeper.
+                let line_indent = format!("{line_indent}{}", config.wrap_indent.as_string());
+                for value in array.iter_mut() {
+                    self.visit_value(value, 0, ("", ""), &line_indent, config);
+                }
+            }
+            Value::InlineTable(_) => {
+                if width_at(offset, &value_text(value, default_decor)) <= max_width {
+                    return;
+                }
+
+                let prefix = value
+                    .decor()
+                    .prefix()
+                    .unwrap_or(default_decor.0)
+                    .to_string();
+                let table = value.as_inline_table_mut().unwrap();
+                let len = table.len();
+
+                // Skip the opening brace and each `key =` to get the offset of the values.
+                let mut offset = column_after(offset, &prefix) + 1;
+                for (idx, (key, value)) in table.iter_mut().enumerate() {
+                    let default_decor = match idx + 1 == len {
+                        true => TRAILING_VALUE_DECOR,
+                        false => VALUE_DECOR,
+                    };
+
+                    offset = column_after(offset, &key_mut_text(&key, INLINE_KEY_DECOR)) + 1;
+                    self.visit_value(value, offset, default_decor, line_indent, config);
+                    offset = column_after(offset, &value_text(value, default_decor)) + 1;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Writes the values of the array on multiple lines, the closing bracket is indented with `line_indent`.
+    fn wrap_array(
+        &self,
+        array: &mut Array,
+        comments: &ArrayComments,
+        line_indent: &str,
+        config: &TomlFormatConfig,
+    ) {
+        let indent = config.wrap_indent.as_string();
+
+        match config.wrap_packing {
+            ArrayPacking::Fill if comments.is_empty() => fill_array(
+                array,
+                &indent,
+                line_indent,
+                config.wrap_array.unwrap(),
+                config.wrap_trailing_comma,
+            ),
+            _ => comments.wrap_array(array, &indent, line_indent, config.wrap_trailing_comma),
         }
     }
 }
 
-/// If a root-level key-value pair is to long, create the table as a separate section.
+/// If a key-value pair is to long, create the table as a separate section.
 ///
 /// ```toml
 /// [dependency]
@@ -418,10 +844,12 @@ impl WrapArray {
 /// path="some_very_long_path_to_directory"
 /// git="https://github.come/some_weird_long_repository_name"
 /// ```
-pub struct InlineTableWrap {
-    long_tables: Vec<(String, String, Item)>,
-    current_section_key: String,
-}
+///
+/// Tables are exploded at any depth, e.g. within `[target.'cfg(unix)'.dependencies]` or a `[[bin]]` entry,
+/// the new section is placed under the table the key belongs to.
+/// The comments above the key are placed above the new header and the comment after the value after it.
+#[derive(Default)]
+pub struct InlineTableWrap;
 
 impl TomlFormatter for InlineTableWrap {
     fn visit_document(
@@ -429,23 +857,25 @@ impl TomlFormatter for InlineTableWrap {
         toml_document: &mut Document,
         config: &TomlFormatConfig,
     ) -> anyhow::Result<()> {
-        iter_sections_as_tables(toml_document, |section_key, section| {
+        let max_width = config.wrap_table.unwrap();
+        // The indentation [TableIndentation] writes before the keys of sub-tables and arrays of tables.
+        let table_indent = config.table_indent.map(|indent| indent.as_string());
+        let table_indent = table_indent.as_deref();
+
+        for (section_key, section) in toml_document.iter_mut() {
             // Package section should remain as it is written.
-            if section_key.get() != "package" {
-                self.current_section_key = section_key.to_owned();
-                self.fmt_table(section, config.wrap_table.unwrap());
+            if section_key.get() == "package" {
+                continue;
             }
-        });
-
-        let mut long_tables = vec![];
 
-        std::mem::swap(&mut self.long_tables, &mut long_tables);
-
-        long_tables.into_iter().for_each(|(section, key, table)| {
-            if let Item::Value(Value::InlineTable(table)) = table {
-                toml_document[&section][&key] = Item::Table(table.into_table());
+            match section {
+                Item::Table(table) => Self::fmt_table(table, 0, None, table_indent, max_width),
+                Item::ArrayOfTables(tables) => tables.iter_mut().for_each(|table| {
+                    Self::fmt_table(table, 0, table_indent, table_indent, max_width)
+                }),
+                Item::Value(_) | Item::None => {}
             }
-        });
+        }
 
         Ok(())
     }
@@ -453,51 +883,1065 @@ impl TomlFormatter for InlineTableWrap {
 
 impl InlineTableWrap {
     pub fn new() -> Self {
-        Self {
-            long_tables: Vec::new(),
-            current_section_key: String::new(),
-        }
+        Self
     }
 
-    fn fmt_table(&mut self, table: &mut dyn TableLike, max_width: usize) {
-        let mut long_table_keys = vec![];
-
-        table.iter_mut().for_each(|(key, node)| {
-            if let Some(table) = node.as_inline_table_mut() {
-                // Length of key doesn't include decor. Length of array does. So we add 2 (" =").
-                if key.get().len() + 1 + table.to_string().len() > max_width {
-                    long_table_keys.push(key.get().to_owned());
+    /// Explodes the long inline tables of the table, `offset` is the width of the parents of a dotted table.
+    ///
+    /// `line_indent` is the indentation [TableIndentation] writes before the keys of the table,
+    /// and `table_indent` the one it writes before the keys of its sub-tables.
+    fn fmt_table(
+        table: &mut Table,
+        offset: usize,
+        line_indent: Option<&str>,
+        table_indent: Option<&str>,
+        max_width: usize,
+    ) {
+        // The key as it is written once indented, the first line of a dotted key already includes the indentation in `offset`.
+        let key_text = |key: &KeyMut, default: (&str, &str)| {
+            let text = key_mut_text(key, default);
+            match line_indent.filter(|_| offset == 0) {
+                Some(indent) => {
+                    let line = text.rsplit('\n').next().unwrap_or_default();
+                    format!("{indent}{}", line.trim_start())
                 }
+                None => text,
             }
-        });
+        };
 
-        long_table_keys.into_iter().for_each(|key| {
-            let item = table.remove(&key).unwrap();
-            self.long_tables
-                .push((self.current_section_key.clone(), key, item));
-        });
+        let long_table_keys = table
+            .iter_mut()
+            .filter_map(|(key, item)| {
+                let value = item.as_value().filter(|value| value.is_inline_table())?;
+                let width = key_value_width(offset, &key_text(&key, KEY_DECOR), value);
 
-        table.iter_mut().for_each(|(_, node)| {
-            self.visit_item(node, max_width);
-        });
-    }
+                (width > max_width).then(|| key.get().to_owned())
+            })
+            .collect::<Vec<String>>();
+
+        // Replacing the item keeps the key, and with it its position within the table.
+        for key in long_table_keys {
+            let leading = table
+                .key_decor(&key)
+                .map(|decor| Comments::from_decor(decor).leading)
+                .unwrap_or_default();
+
+            if let Some(Item::Value(Value::InlineTable(inline_table))) = table.get_mut(&key) {
+                let comments = Comments {
+                    leading,
+                    trailing: Comments::from_decor(inline_table.decor()).trailing,
+                };
+
+                let inline_table = std::mem::take(inline_table);
+                let mut sub_table = inline_table.into_table();
+
+                // The comments of `a = { .. } # comment` are written around the section header.
+                if !comments.is_empty() {
+                    sub_table
+                        .decor_mut()
+                        .set_prefix(format!("\n{}", comments.prefix()));
+                    sub_table.decor_mut().set_suffix(comments.suffix());
+                }
 
-    fn visit_item(&mut self, item: &mut Item, max_width: usize) {
-        match item {
-            Item::Value(value) => match value {
-                Value::InlineTable(inline_table) => self.fmt_table(inline_table, max_width),
-                Value::String(_)
-                | Value::Integer(_)
-                | Value::Float(_)
-                | Value::Boolean(_)
-                | Value::Datetime(_)
-                | Value::Array(_) => {}
-            },
-            Item::Table(table) => self.fmt_table(table, max_width),
-            Item::ArrayOfTables(array) => {
-                array.iter_mut().for_each(|x| self.fmt_table(x, max_width))
+                table[&key] = Item::Table(sub_table);
+
+                // The key is now written in the section header, e.g. `[dependencies.a]`, without the spacing of `a = `.
+                if let Some(decor) = table.key_decor_mut(&key) {
+                    decor.clear();
+                }
+            }
+        }
+
+        for (key, item) in table.iter_mut() {
+            match item {
+                Item::Table(sub_table) if sub_table.is_dotted() => {
+                    let offset = column_after(offset, &key_text(&key, ("", ""))) + 1;
+                    Self::fmt_table(sub_table, offset, line_indent, table_indent, max_width);
+                }
+                Item::Table(sub_table) => {
+                    Self::fmt_table(sub_table, 0, table_indent, table_indent, max_width)
+                }
+                Item::ArrayOfTables(tables) => tables.iter_mut().for_each(|table| {
+                    Self::fmt_table(table, 0, table_indent, table_indent, max_width)
+                }),
+                Item::Value(_) | Item::None => {}
             }
-            Item::None => {}
         }
     }
 }
+
+/// The inverse of [InlineTableWrap], collapses a short dependency table back into an inline table.
+///
+/// ```toml
+/// [dependencies.a]
+/// version = "0.4.1"
+/// features = ["a"]
+/// ```
+///
+/// to
+///
+/// ```toml
+/// [dependencies]
+/// a = { version = "0.4.1", features = ["a"] }
+/// ```
+///
+/// A table is only collapsed when it contains no sub tables, fits within [TomlFormatConfig::wrap_table] and has no comments that would be lost.
+/// A table skipped by a [Directive](crate::directives::Directive) is left as it is.
+#[derive(Default)]
+pub struct InlineTableCollapse {
+    directives: Directives,
+}
+
+impl TomlFormatter for InlineTableCollapse {
+    fn visit_document(
+        &mut self,
+        toml_document: &mut Document,
+        config: &TomlFormatConfig,
+    ) -> anyhow::Result<()> {
+        let max_width = config.wrap_table.unwrap();
+
+        iter_dependency_tables_mut(toml_document, |path, dependencies| {
+            self.visit_dependencies(path, dependencies, max_width);
+        });
+
+        Ok(())
+    }
+
+    fn set_directives(&mut self, directives: &Directives) {
+        self.directives = directives.clone();
+    }
+}
+
+impl InlineTableCollapse {
+    fn visit_dependencies(&self, path: &HeaderPath, table: &mut Table, max_width: usize) {
+        // The keys of a skipped table are left as they are, the sub tables are not collapsed into it.
+        if self.directives.skips_header(path) {
+            return;
+        }
+
+        let collapsed = table
+            .iter()
+            .filter_map(|(key, item)| match item {
+                Item::Table(sub_table) => {
+                    let mut sub_path = path.clone();
+                    sub_path.push((key.to_string(), None));
+                    if self.directives.skips_header(&sub_path) {
+                        return None;
+                    }
+
+                    Self::collapse(table.get_key_value(key)?.0, sub_table, max_width)
+                        .map(|inline_table| (key.to_string(), inline_table))
+                }
+                _ => None,
+            })
+            .collect::<Vec<(String, InlineTable)>>();
+
+        if !collapsed.is_empty() && table.is_implicit() {
+            // E.g. `[dependencies.a]` without a `[dependencies]` section, the values need a visible section.
+            table.set_implicit(false);
+        }
+
+        for (key, inline_table) in collapsed {
+            if let Some((mut key, _)) = table.remove_entry(&key) {
+                key.decor_mut().clear();
+                table.insert_formatted(&key, Item::Value(Value::InlineTable(inline_table)));
+            }
+        }
+    }
+
+    /// Returns the inline table if the table can be collapsed.
+    fn collapse(key: &Key, table: &Table, max_width: usize) -> Option<InlineTable> {
+        let is_scalar = |item: &Item| match item {
+            Item::Value(Value::InlineTable(_)) => false,
+            Item::Value(Value::Array(array)) => array
+                .iter()
+                .all(|value| !value.is_array() && !value.is_inline_table()),
+            Item::Value(_) => true,
+            _ => false,
+        };
+
+        if table.is_dotted()
+            || table.is_empty()
+            || !table.iter().all(|(_, item)| is_scalar(item))
+            || Self::has_comments(table)
+        {
+            return None;
+        }
+
+        let mut inline_table = table.clone().into_inline_table();
+        inline_table.fmt();
+        inline_table.decor_mut().set_prefix(" ");
+        inline_table.decor_mut().set_suffix("");
+
+        // The key is written without its decor, the same line length as used by [InlineTableWrap] so the table is not wrapped again.
+        let mut key = key.clone();
+        key.decor_mut().clear();
+
+        let value = Value::InlineTable(inline_table.clone());
+        if key_value_width(0, &key_text(&key, KEY_DECOR), &value) > max_width {
+            return None;
+        }
+
+        Some(inline_table)
+    }
+
+    fn has_comments(table: &Table) -> bool {
+        has_comments(table.decor())
+            || table.iter().any(|(key, item)| {
+                let key_has_comment = table.key_decor(key).is_some_and(has_comments);
+
+                let value_has_comment = match item.as_value() {
+                    Some(Value::Array(array)) => {
+                        has_comments(array.decor()) || !ArrayComments::from_array(array).is_empty()
+                    }
+                    Some(value) => has_comments(value.decor()),
+                    None => false,
+                };
+
+                key_has_comment || value_has_comment
+            })
+    }
+}
+
+/// Normalizes the way dependencies are specified in all dependency tables, including target-specific ones.
+///
+/// ```toml
+/// [dependencies]
+/// a = { version = "0.1" }
+/// b = { workspace = true }
+/// ```
+///
+/// to (with [DependencyStyle::Short] and [TomlFormatConfig::dotted_workspace_dependencies])
+///
+/// ```toml
+/// [dependencies]
+/// a = "0.1"
+/// b.workspace = true
+/// ```
+pub struct DependencyShorthand;
+
+impl TomlFormatter for DependencyShorthand {
+    fn visit_document(
+        &mut self,
+        toml_document: &mut Document,
+        config: &TomlFormatConfig,
+    ) -> anyhow::Result<()> {
+        iter_dependency_tables_mut(toml_document, |_, dependencies| {
+            let keys = dependencies
+                .iter()
+                .map(|(key, _)| key.to_string())
+                .collect::<Vec<String>>();
+
+            for key in keys {
+                if config.dotted_workspace_dependencies
+                    && Self::make_workspace_dotted(dependencies, &key)
+                {
+                    continue;
+                }
+
+                if let Some(Item::Value(value)) = dependencies.get_mut(&key) {
+                    match config.dependency_style {
+                        Some(DependencyStyle::Short) => Self::make_short(value),
+                        Some(DependencyStyle::Table) => Self::make_table(value),
+                        None => {}
+                    }
+                }
+            }
+        });
+
+        Ok(())
+    }
+}
+
+impl DependencyShorthand {
+    /// `a = { version = "0.1" }` to `a = "0.1"`.
+    fn make_short(value: &mut Value) {
+        let inline_table = match value {
+            Value::InlineTable(inline_table) if inline_table.len() == 1 => inline_table,
+            _ => return,
+        };
+
+        if let Some(Value::String(version)) = inline_table.get("version") {
+            let mut version = Value::String(version.clone());
+            *version.decor_mut() = inline_table.decor().clone();
+            *value = version;
+        }
+    }
+
+    /// `a = "0.1"` to `a = { version = "0.1" }`.
+    fn make_table(value: &mut Value) {
+        if !value.is_str() {
+            return;
+        }
+
+        let decor = value.decor().clone();
+
+        let mut version = value.clone();
+        version.decor_mut().clear();
+
+        let mut inline_table = InlineTable::new();
+        inline_table.insert("version", version);
+        inline_table.fmt();
+        *inline_table.decor_mut() = decor;
+
+        *value = Value::InlineTable(inline_table);
+    }
+
+    /// `a = { workspace = true }` to `a.workspace = true`, returns whether the dependency was changed.
+    fn make_workspace_dotted(dependencies: &mut Table, key: &str) -> bool {
+        let decor = match dependencies.get(key) {
+            Some(Item::Value(Value::InlineTable(inline_table)))
+                if inline_table.len() == 1
+                    && inline_table
+                        .get("workspace")
+                        .and_then(|value| value.as_bool())
+                        == Some(true) =>
+            {
+                inline_table.decor().clone()
+            }
+            _ => return false,
+        };
+
+        let mut workspace = value(true);
+        if let Some(workspace) = workspace.as_value_mut() {
+            *workspace.decor_mut() = decor;
+        }
+
+        let mut dotted = Table::new();
+        dotted.set_dotted(true);
+        dotted.insert("workspace", workspace);
+
+        *dependencies.get_mut(key).unwrap() = Item::Table(dotted);
+
+        // A dotted key is written as `a.workspace`, whitespace after `a` would result in `a .workspace`.
+        if let Some(decor) = dependencies.key_decor_mut(key) {
+            decor.set_suffix("");
+        }
+
+        true
+    }
+}
+
+/// Expands or collapses dotted keys according to the configured [DottedKeyStyle].
+///
+/// With [DottedKeyStyle::Expand] dotted keys in dependency tables become inline tables,
+/// other dotted keys become sub-tables and dotted keys within inline tables become nested inline tables.
+///
+/// ```toml
+/// [package]
+/// metadata.docs.rs.all-features = true
+///
+/// [dependencies]
+/// a.workspace = true
+/// ```
+///
+/// to
+///
+/// ```toml
+/// [package]
+///
+/// [package.metadata.docs.rs]
+/// all-features = true
+///
+/// [dependencies]
+/// a = { workspace = true }
+/// ```
+///
+/// [DottedKeyStyle::Collapse] does the opposite for sub-tables and nested inline tables containing a single key.
+/// Tables with a comment after their header, and the keys and tables skipped by a [Directive](crate::directives::Directive), are left as they are.
+#[derive(Default)]
+pub struct DottedKeyFormatting {
+    directives: Directives,
+}
+
+impl TomlFormatter for DottedKeyFormatting {
+    fn visit_document(
+        &mut self,
+        toml_document: &mut Document,
+        config: &TomlFormatConfig,
+    ) -> anyhow::Result<()> {
+        let style = config.dotted_keys.unwrap();
+
+        match style {
+            DottedKeyStyle::Expand => {
+                self.expand_table(toml_document.as_table_mut(), &mut vec![], false)
+            }
+            DottedKeyStyle::Collapse => {
+                self.collapse_table(toml_document.as_table_mut(), &mut vec![], false);
+            }
+        }
+
+        iter_values_mut(toml_document, |value| {
+            if let Value::InlineTable(inline_table) = value {
+                Self::visit_inline_table(inline_table, style);
+            }
+        });
+
+        Ok(())
+    }
+
+    fn set_directives(&mut self, directives: &Directives) {
+        self.directives = directives.clone();
+    }
+}
+
+impl DottedKeyFormatting {
+    /// Expands the dotted keys in the table at the given path, recursively for all sub-tables.
+    fn expand_table(&self, table: &mut Table, path: &mut HeaderPath, is_dependency_table: bool) {
+        for (mut key, item) in table.iter_mut() {
+            if self.directives.skips_key(path, key.get()) {
+                continue;
+            }
+
+            match item {
+                Item::Table(sub_table) if sub_table.is_dotted() && is_dependency_table => {
+                    if let Some(inline_table) = Self::expand_to_inline_table(sub_table) {
+                        *item = Item::Value(Value::InlineTable(inline_table));
+                        key.decor_mut().set_suffix(" ");
+                    }
+                }
+                Item::Table(sub_table) => {
+                    let is_dependency_table = DependencySection::VARIANTS.contains(&key.get());
+                    let was_dotted = sub_table.is_dotted();
+
+                    sub_table.set_dotted(false);
+                    path.push((key.get().to_string(), None));
+                    self.expand_table(sub_table, path, is_dependency_table);
+                    path.pop();
+
+                    if was_dotted {
+                        // Only the tables with values get a header, e.g. `[a.b.c]` for `b.c.d = 1` in `[a]`.
+                        sub_table.set_implicit(sub_table.get_values().is_empty());
+
+                        // The comments above the dotted key are placed above the first new header.
+                        let prefix = key.decor().prefix().unwrap_or_default().to_string();
+                        Self::first_header_mut(sub_table)
+                            .decor_mut()
+                            .set_prefix(format!("\n{}", prefix.trim_start()));
+                        key.decor_mut().clear();
+                    }
+                }
+                Item::ArrayOfTables(tables) => {
+                    for (idx, table) in tables.iter_mut().enumerate() {
+                        path.push((key.get().to_string(), Some(idx)));
+                        self.expand_table(table, path, false);
+                        path.pop();
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Returns the first table that is displayed with a header, e.g. `[a.b.c]` for `a`.
+    fn first_header_mut(table: &mut Table) -> &mut Table {
+        if !table.is_implicit() {
+            return table;
+        }
+
+        let first_sub_table = table
+            .iter()
+            .find(|(_, item)| item.as_table().is_some_and(|table| !table.is_dotted()))
+            .map(|(key, _)| key.to_string());
+
+        match first_sub_table {
+            Some(key) => Self::first_header_mut(table[&key].as_table_mut().unwrap()),
+            None => table,
+        }
+    }
+
+    /// Returns the inline table of `a.workspace = true`, only the trailing comment of the last value can be kept.
+    fn expand_to_inline_table(table: &Table) -> Option<InlineTable> {
+        let values = table.get_values();
+
+        let comment = values
+            .last()
+            .map(|(_, value)| Comments::from_decor(value.decor()).suffix())
+            .unwrap_or_default();
+
+        let has_other_comments = values.iter().enumerate().any(|(idx, (keys, value))| {
+            keys.iter().any(|key| has_comments(key.decor()))
+                || !Comments::from_decor(value.decor()).leading.is_empty()
+                || (idx + 1 < values.len() && has_comments(value.decor()))
+        });
+
+        if has_other_comments {
+            return None;
+        }
+
+        let mut inline_table = table.clone().into_inline_table();
+        inline_table.decor_mut().set_prefix(" ");
+        inline_table.decor_mut().set_suffix(comment);
+
+        Some(inline_table)
+    }
+
+    /// Collapses the sub-tables, bottom up, containing a single key into a dotted key, e.g. `[a.b]` with `c = 1` into `b.c = 1` within `[a]`.
+    ///
+    /// Keys are only collapsed into a table with a header, or into a dependency table which then gets a header.
+    /// A skipped table is neither collapsed nor gets the keys of its sub-tables.
+    fn collapse_table(&self, table: &mut Table, path: &mut HeaderPath, can_hold_dotted_keys: bool) {
+        let can_hold_dotted_keys = can_hold_dotted_keys && !self.directives.skips_header(path);
+        let mut collapsed = false;
+        // Whether a key is written before the current one, below the header of the table.
+        let mut has_keys = false;
+
+        for (mut key, item) in table.iter_mut() {
+            let is_dependency_table = DependencySection::VARIANTS.contains(&key.get());
+
+            let sub_table = match item {
+                Item::Value(_) => {
+                    has_keys = true;
+                    continue;
+                }
+                Item::Table(sub_table) if sub_table.is_dotted() => {
+                    has_keys = true;
+                    continue;
+                }
+                Item::Table(sub_table) => sub_table,
+                Item::ArrayOfTables(tables) => {
+                    for (idx, table) in tables.iter_mut().enumerate() {
+                        path.push((key.get().to_string(), Some(idx)));
+                        self.collapse_table(table, path, true);
+                        path.pop();
+                    }
+                    continue;
+                }
+                _ => continue,
+            };
+
+            path.push((key.get().to_string(), None));
+            let can_hold_dotted_keys_in_sub_table = !sub_table.is_implicit() || is_dependency_table;
+            self.collapse_table(sub_table, path, can_hold_dotted_keys_in_sub_table);
+            let is_skipped = self.directives.skips_header(path);
+            path.pop();
+
+            if !can_hold_dotted_keys || is_skipped || !Self::can_collapse(sub_table) {
+                continue;
+            }
+
+            // The comments above the header are placed above the dotted key,
+            // and a blank line before the header keeps separating them from the keys before.
+            let prefix = Self::make_dotted(sub_table);
+            let comments = prefix.trim_start_matches('\n');
+            let blank_line = if has_keys && comments.len() < prefix.len() {
+                "\n"
+            } else {
+                ""
+            };
+            key.decor_mut().clear();
+            key.decor_mut()
+                .set_prefix(format!("{blank_line}{comments}"));
+
+            collapsed = true;
+            has_keys = true;
+        }
+
+        if collapsed && table.is_implicit() {
+            // E.g. `[dependencies.a]` without a `[dependencies]` section, the dotted key needs a visible section.
+            table.set_implicit(false);
+        }
+    }
+
+    /// Returns whether the table holds a single key, either directly or through implicit tables, e.g. `[a.b.c]` within `[a]`.
+    fn can_collapse(table: &Table) -> bool {
+        let (key, item) = match table.iter().collect::<Vec<(&str, &Item)>>().as_slice() {
+            [(key, item)] => (*key, *item),
+            _ => return false,
+        };
+
+        let key_prefix = table
+            .key_decor(key)
+            .and_then(Decor::prefix)
+            .unwrap_or_default();
+
+        if !comment_lines(key_prefix).is_empty()
+            || Comments::from_decor(table.decor()).trailing.is_some()
+        {
+            return false;
+        }
+
+        match item {
+            Item::Value(_) => true,
+            Item::Table(sub_table) if sub_table.is_dotted() => true,
+            Item::Table(sub_table) => table.is_implicit() && Self::can_collapse(sub_table),
+            _ => false,
+        }
+    }
+
+    /// Makes the table and its implicit sub-tables dotted, returns the prefix of the header that is removed.
+    fn make_dotted(table: &mut Table) -> String {
+        let mut prefix = if table.is_implicit() {
+            String::new()
+        } else {
+            table.decor().prefix().unwrap_or_default().to_string()
+        };
+
+        table.set_dotted(true);
+
+        for (mut key, item) in table.iter_mut() {
+            key.decor_mut().clear();
+
+            if let Item::Table(sub_table) = item {
+                if !sub_table.is_dotted() {
+                    prefix.push_str(&Self::make_dotted(sub_table));
+                }
+            }
+        }
+
+        prefix
+    }
+
+    /// Expands or collapses the dotted keys of the inline tables within the inline table, bottom up.
+    fn visit_inline_table(inline_table: &mut InlineTable, style: DottedKeyStyle) {
+        let mut changed = false;
+
+        for (_, value) in inline_table.iter_mut() {
+            if let Value::InlineTable(nested) = value {
+                Self::visit_inline_table(nested, style);
+
+                let dotted = match style {
+                    DottedKeyStyle::Expand => false,
+                    DottedKeyStyle::Collapse => nested.len() == 1 || nested.is_dotted(),
+                };
+
+                if nested.is_dotted() != dotted {
+                    nested.set_dotted(dotted);
+                    nested.fmt();
+                    changed = true;
+                }
+            }
+        }
+
+        if changed {
+            inline_table.fmt();
+        }
+    }
+}
+
+/// Rewrites the `version` requirement of dependencies to the configured [crate::version::VersionFormat].
+///
+/// ```toml
+/// [dependencies]
+/// a = "^1"
+/// b = { version = ">= 1.2,<2" }
+/// ```
+///
+/// to (with 3 components and [crate::version::VersionFormat::normalize_comparators])
+///
+/// ```toml
+/// [dependencies]
+/// a = "1.0.0"
+/// b = { version = ">=1.2, <2" }
+/// ```
+///
+/// With [TomlFormatConfig::lint_wildcard_versions] a warning is reported for requirements like `*` or `1.*`.
+#[derive(Default)]
+pub struct VersionRequirementFormatting {
+    warnings: Vec<String>,
+}
+
+impl TomlFormatter for VersionRequirementFormatting {
+    fn visit_document(
+        &mut self,
+        toml_document: &mut Document,
+        config: &TomlFormatConfig,
+    ) -> anyhow::Result<()> {
+        iter_dependency_tables_mut(toml_document, |_, dependencies| {
+            for (key, item) in dependencies.iter_mut() {
+                let version = match Self::version_mut(item) {
+                    Some(version) => version,
+                    None => continue,
+                };
+
+                let mut requirement = match version.as_str().and_then(VersionRequirement::parse) {
+                    Some(requirement) => requirement,
+                    None => continue,
+                };
+
+                if config.lint_wildcard_versions && requirement.has_wildcard() {
+                    self.warnings.push(format!(
+                        "Dependency `{}` has a wildcard version requirement `{requirement}`.",
+                        key.get()
+                    ));
+                }
+
+                let format = match &config.version_format {
+                    Some(format) if requirement.is_caret() || format.normalize_comparators => {
+                        format
+                    }
+                    _ => continue,
+                };
+
+                requirement.normalize(format);
+
+                let normalized = requirement.to_string();
+                if version.as_str() != Some(normalized.as_str()) {
+                    let decor = version.decor().clone();
+                    *version = Value::from(normalized);
+                    *version.decor_mut() = decor;
+                }
+            }
+        });
+
+        Ok(())
+    }
+
+    fn take_warnings(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.warnings)
+    }
+}
+
+impl VersionRequirementFormatting {
+    /// Returns the version of `a = "0.1"`, `a = { version = "0.1" }` or `[dependencies.a]`.
+    fn version_mut(item: &mut Item) -> Option<&mut Value> {
+        match item {
+            Item::Value(Value::String(_)) => item.as_value_mut(),
+            Item::Value(Value::InlineTable(inline_table)) => inline_table.get_mut("version"),
+            Item::Table(table) => table.get_mut("version").and_then(Item::as_value_mut),
+            _ => None,
+        }
+    }
+}
+
+/// Wraps a long `package.description` over multiple lines.
+///
+/// The description is written as a multi-line basic string where each line ends with a line continuation (`\`),
+/// hence the parsed description stays exactly the same.
+///
+/// ```toml
+/// [package]
+/// description = "A very long description that does not fit within the configured width."
+/// ```
+///
+/// to
+///
+/// ```toml
+/// [package]
+/// description = """
+/// A very long description that does not fit \
+/// within the configured width."""
+/// ```
+///
+/// A description that can't be wrapped, e.g. a single long word, is left as it is.
+/// When the wrapped description doesn't parse back to the same text a warning is reported instead.
+#[derive(Default)]
+pub struct WrapDescription {
+    warnings: Vec<String>,
+}
+
+impl TomlFormatter for WrapDescription {
+    fn visit_document(
+        &mut self,
+        toml_document: &mut Document,
+        config: &TomlFormatConfig,
+    ) -> anyhow::Result<()> {
+        let width = config.wrap_description.unwrap();
+
+        let description = match toml_document
+            .get_mut("package")
+            .and_then(|package| package.get_mut("description"))
+            .and_then(Item::as_value_mut)
+        {
+            // Descriptions with newlines are left to [MultilineStrings].
+            Some(description) if description.as_str().is_some_and(|d| !d.contains('\n')) => {
+                description
+            }
+            _ => return Ok(()),
+        };
+
+        let mut line = description.clone();
+        line.decor_mut().clear();
+
+        // Only wrap the description when the `description = "..."` line is too long.
+        if key_value_width(0, "description ", &line) <= width {
+            return Ok(());
+        }
+
+        let value = description.as_str().unwrap().to_string();
+
+        let wrapped = match Self::wrap(&value, width) {
+            Some(wrapped) => wrapped,
+            None => return Ok(()),
+        };
+
+        let mut wrapped = match wrapped.parse::<Value>() {
+            Ok(wrapped) => wrapped,
+            Err(e) => {
+                self.warnings
+                    .push(format!("Failed to wrap the description. {e}"));
+                return Ok(());
+            }
+        };
+
+        // Never change the description itself.
+        if wrapped.as_str() != Some(value.as_str()) {
+            self.warnings
+                .push("Wrapping the description would change its value.".to_string());
+            return Ok(());
+        }
+
+        *wrapped.decor_mut() = description.decor().clone();
+        *description = wrapped;
+
+        Ok(())
+    }
+
+    fn take_warnings(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.warnings)
+    }
+}
+
+impl WrapDescription {
+    /// Returns the multi-line basic string representation of the value wrapped at spaces,
+    /// or `None` when the value fits on a single line of the multi-line string and can't be wrapped.
+    fn wrap(value: &str, width: usize) -> Option<String> {
+        let mut lines = vec![];
+        let mut line = String::new();
+
+        let segments = Self::segments(value);
+        let last = segments.len() - 1;
+
+        for (idx, segment) in segments.into_iter().enumerate() {
+            let segment = escape_basic_string(segment, false);
+
+            // Account for the line continuation, or the closing quotes after the last segment, at the end of the line.
+            let end = if idx == last { 3 } else { 1 };
+            if !line.is_empty() && display_width(&line) + display_width(&segment) + end > width {
+                lines.push(std::mem::take(&mut line));
+            }
+
+            line.push_str(&segment);
+        }
+
+        lines.push(line);
+
+        if lines.len() < 2 {
+            return None;
+        }
+
+        Some(format!("\"\"\"\n{}\"\"\"", lines.join("\\\n")))
+    }
+
+    /// Splits the value into words including their trailing spaces, e.g. `"a  b c"` into `["a  ", "b ", "c"]`.
+    ///
+    /// A line continuation trims all whitespace at the start of the next line, so lines may only be broken
+    /// after the spaces of a segment.
+    fn segments(value: &str) -> Vec<&str> {
+        let mut segments = vec![];
+        let mut start = 0;
+        let mut previous_is_space = false;
+
+        for (idx, c) in value.char_indices() {
+            if previous_is_space && !c.is_whitespace() {
+                segments.push(&value[start..idx]);
+                start = idx;
+            }
+
+            previous_is_space = c == ' ';
+        }
+
+        segments.push(&value[start..]);
+        segments
+    }
+}
+
+/// Rewrites strings containing newlines into multi-line strings.
+///
+/// ```toml
+/// [package.metadata]
+/// notes = "first line\nsecond line"
+/// ```
+///
+/// to
+///
+/// ```toml
+/// [package.metadata]
+/// notes = """
+/// first line
+/// second line"""
+/// ```
+///
+/// When [TomlFormatConfig::prefer_literal_multiline_strings] is enabled, a literal string (`'''`) is used if the string doesn't need escaping.
+/// A string that doesn't parse back to the same value is left as it is and a warning is reported.
+#[derive(Default)]
+pub struct MultilineStrings {
+    warnings: Vec<String>,
+}
+
+impl TomlFormatter for MultilineStrings {
+    fn visit_document(
+        &mut self,
+        toml_document: &mut Document,
+        config: &TomlFormatConfig,
+    ) -> anyhow::Result<()> {
+        let warnings = &mut self.warnings;
+
+        iter_values_mut(toml_document, |value| {
+            if let Err(e) = Self::visit_value(value, config) {
+                warnings.push(format!("{e} The string is left as it is."));
+            }
+        });
+
+        Ok(())
+    }
+
+    fn take_warnings(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.warnings)
+    }
+}
+
+impl MultilineStrings {
+    fn visit_value(value: &mut Value, config: &TomlFormatConfig) -> anyhow::Result<()> {
+        let string = match value {
+            Value::String(string) => string,
+            _ => return Ok(()),
+        };
+
+        let raw = string.to_repr().as_raw().to_string();
+        let is_multiline = raw.starts_with("\"\"\"") || raw.starts_with("'''");
+
+        if !string.value().contains('\n') || is_multiline {
+            return Ok(());
+        }
+
+        let original = string.value().clone();
+
+        let mut multiline = Self::to_multiline(&original, config)
+            .parse::<Value>()
+            .map_err(|e| anyhow::anyhow!("Failed to create a multi-line string. {e}"))?;
+
+        // Never change the string itself.
+        if multiline.as_str() != Some(original.as_str()) {
+            anyhow::bail!("Creating a multi-line string changed its value.");
+        }
+
+        *multiline.decor_mut() = value.decor().clone();
+        *value = multiline;
+
+        Ok(())
+    }
+
+    /// Returns the multi-line string representation of the value.
+    /// The newline after the opening quotes is trimmed when the string is parsed.
+    fn to_multiline(value: &str, config: &TomlFormatConfig) -> String {
+        if config.prefer_literal_multiline_strings && Self::is_literal_compatible(value) {
+            format!("'''\n{value}'''")
+        } else {
+            format!("\"\"\"\n{}\"\"\"", escape_basic_string(value, true))
+        }
+    }
+
+    /// A multi-line literal string can't contain `'''`, control characters other than tab or newline and may not end with a quote.
+    fn is_literal_compatible(value: &str) -> bool {
+        !value.contains("'''")
+            && !value.ends_with('\'')
+            && !value
+                .chars()
+                .any(|c| (c <= '\u{1f}' && c != '\t' && c != '\n') || c == '\u{7f}')
+    }
+}
+
+/// Normalizes the quotes of single-line strings to the configured [QuoteStyle], never changing the parsed string.
+///
+/// ```toml
+/// [dependencies]
+/// a = { version = '0.1', path = 'C:\path' }
+/// ```
+///
+/// to (with [QuoteStyle::Double])
+///
+/// ```toml
+/// [dependencies]
+/// a = { version = "0.1", path = 'C:\path' }
+/// ```
+///
+/// A string that doesn't parse back to the same value is left as it is and a warning is reported.
+#[derive(Default)]
+pub struct StringQuoteFormatting {
+    warnings: Vec<String>,
+}
+
+impl TomlFormatter for StringQuoteFormatting {
+    fn visit_document(
+        &mut self,
+        toml_document: &mut Document,
+        config: &TomlFormatConfig,
+    ) -> anyhow::Result<()> {
+        let quote_style = config.string_quote_style.unwrap();
+        let warnings = &mut self.warnings;
+
+        iter_values_mut(toml_document, |value| {
+            if let Err(e) = Self::visit_value(value, quote_style) {
+                warnings.push(format!("{e} The string is left as it is."));
+            }
+        });
+
+        Ok(())
+    }
+
+    fn take_warnings(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.warnings)
+    }
+}
+
+impl StringQuoteFormatting {
+    fn visit_value(value: &mut Value, quote_style: QuoteStyle) -> anyhow::Result<()> {
+        let string = match value {
+            Value::String(string) => string,
+            _ => return Ok(()),
+        };
+
+        let raw = string.to_repr().as_raw().to_string();
+
+        // Multi-line strings are left as they are.
+        if raw.starts_with("\"\"\"") || raw.starts_with("'''") {
+            return Ok(());
+        }
+
+        let original = string.value().clone();
+        let quoted = Self::quote(&original, quote_style);
+
+        if quoted == raw {
+            return Ok(());
+        }
+
+        let mut quoted = quoted
+            .parse::<Value>()
+            .map_err(|e| anyhow::anyhow!("Failed to quote string. {e}"))?;
+
+        // Never change the string itself.
+        if quoted.as_str() != Some(original.as_str()) {
+            anyhow::bail!("Changing the quotes of a string changed its value.");
+        }
+
+        *quoted.decor_mut() = value.decor().clone();
+        *value = quoted;
+
+        Ok(())
+    }
+
+    /// Returns the quoted value, a literal string is used for [QuoteStyle::Double] when a basic string would need escaping.
+    fn quote(value: &str, quote_style: QuoteStyle) -> String {
+        let escaped = escape_basic_string(value, false);
+        let needs_escaping = escaped != value;
+
+        let use_literal = match quote_style {
+            QuoteStyle::Double => needs_escaping,
+            QuoteStyle::Single => true,
+        };
+
+        if use_literal && Self::is_literal_compatible(value) {
+            format!("'{value}'")
+        } else {
+            format!("\"{escaped}\"")
+        }
+    }
+
+    /// A literal string can't contain a single quote, newlines or control characters other than tab.
+    fn is_literal_compatible(value: &str) -> bool {
+        !value
+            .chars()
+            .any(|c| c == '\'' || (c <= '\u{1f}' && c != '\t') || c == '\u{7f}')
+    }
+}
@@ -1,21 +1,33 @@
 pub mod cargo_toml;
+pub mod comments;
+pub mod comparator;
+pub mod directives;
 pub mod formatting;
 pub mod ordering;
 pub mod package_order;
 pub mod toml_config;
 pub mod utils;
 pub mod verify;
+pub mod version;
+pub mod width;
 
 pub use formatting::{
-    AppendLineAfterSection, InlineTableWrap, KeyQuoteTrimmer, KeyTrimmer, SectionKeyNameTrimmer,
-    TableFormatting, WrapArray,
+    AlignEntries, AppendLineAfterSection, BlankLineFormatting, DependencyShorthand,
+    DottedKeyFormatting, InlineTableCollapse, InlineTableWrap, KeyQuoteTrimmer, KeyTrimmer,
+    MultilineStrings, SectionKeyNameTrimmer, StringQuoteFormatting, TableFormatting,
+    TableIndentation, VersionRequirementFormatting, WrapArray, WrapDescription,
 };
 pub use ordering::{
-    OrderDependencies, OrderPackageSection, OrderSections, OrderTableKeysAlphabetically,
+    OrderArrayOfTables, OrderArrayValues, OrderDependencies, OrderFeatures, OrderPackageSection,
+    OrderSections, OrderTableKeysAlphabetically,
 };
 
+use comments::HeaderPath;
+use directives::Directives;
+use package_order::DependencySection;
+use strum::VariantNames;
 use toml_config::TomlFormatConfig;
-use toml_edit::{Document, Item, Key, KeyMut, Table};
+use toml_edit::{Document, Item, Key, KeyMut, Table, Value};
 
 pub trait TomlFormatter {
     fn visit_document(
@@ -23,6 +35,16 @@ pub trait TomlFormatter {
         toml_document: &mut Document,
         _config: &TomlFormatConfig,
     ) -> anyhow::Result<()>;
+
+    /// Receives the `# toml-fmt:` directives of the document before it is formatted.
+    ///
+    /// Rules that move or restructure sections keep them to leave the skipped sections in place.
+    fn set_directives(&mut self, _directives: &Directives) {}
+
+    /// Returns and clears the warnings found while visiting the document, e.g. by a lint.
+    fn take_warnings(&mut self) -> Vec<String> {
+        vec![]
+    }
 }
 
 fn iter_sections_as_tables<F: FnMut(&mut KeyMut, &mut Table)>(document: &mut Document, mut cb: F) {
@@ -58,3 +80,79 @@ fn iter_sections_as_items_mut<F: FnMut(&mut KeyMut, &mut Item)>(
         cb(&mut key, section);
     });
 }
+
+/// Recursively iterates all values of the document, including the values within arrays and inline tables.
+fn iter_values_mut<F: FnMut(&mut Value)>(document: &mut Document, mut cb: F) {
+    fn visit_item<F: FnMut(&mut Value)>(item: &mut Item, cb: &mut F) {
+        match item {
+            Item::None => {}
+            Item::Value(value) => visit_value(value, cb),
+            Item::Table(table) => table.iter_mut().for_each(|(_, item)| visit_item(item, cb)),
+            Item::ArrayOfTables(tables) => tables
+                .iter_mut()
+                .for_each(|table| table.iter_mut().for_each(|(_, item)| visit_item(item, cb))),
+        }
+    }
+
+    fn visit_value<F: FnMut(&mut Value)>(value: &mut Value, cb: &mut F) {
+        cb(value);
+
+        match value {
+            Value::Array(array) => array.iter_mut().for_each(|value| visit_value(value, cb)),
+            Value::InlineTable(inline_table) => inline_table
+                .iter_mut()
+                .for_each(|(_, value)| visit_value(value, cb)),
+            _ => {}
+        }
+    }
+
+    document
+        .iter_mut()
+        .for_each(|(_, item)| visit_item(item, &mut cb));
+}
+
+/// Iterates the dependency tables of the document, i.e. `[dependencies]`, `[target.'cfg(unix)'.dev-dependencies]`
+/// and `[workspace.dependencies]`, together with the path of their header.
+///
+/// Tables with the same name elsewhere, e.g. `[package.metadata.tool.dependencies]`, are not Cargo's and are skipped.
+fn iter_dependency_tables_mut<F: FnMut(&HeaderPath, &mut Table)>(
+    document: &mut Document,
+    mut cb: F,
+) {
+    fn visit_dependencies<F: FnMut(&HeaderPath, &mut Table)>(
+        path: &mut HeaderPath,
+        table: &mut Table,
+        cb: &mut F,
+    ) {
+        table.iter_mut().for_each(|(key, item)| {
+            if let Item::Table(table) = item {
+                if DependencySection::VARIANTS.contains(&key.get()) {
+                    path.push((key.get().to_string(), None));
+                    cb(path, table);
+                    path.pop();
+                }
+            }
+        });
+    }
+
+    visit_dependencies(&mut vec![], document.as_table_mut(), &mut cb);
+
+    if let Some(Item::Table(targets)) = document.get_mut("target") {
+        targets.iter_mut().for_each(|(key, target)| {
+            if let Item::Table(target) = target {
+                let mut path = vec![("target".to_string(), None), (key.get().to_string(), None)];
+                visit_dependencies(&mut path, target, &mut cb);
+            }
+        });
+    }
+
+    if let Some(Item::Table(workspace)) = document.get_mut("workspace") {
+        if let Some(Item::Table(dependencies)) = workspace.get_mut("dependencies") {
+            let path = vec![
+                ("workspace".to_string(), None),
+                ("dependencies".to_string(), None),
+            ];
+            cb(&path, dependencies);
+        }
+    }
+}
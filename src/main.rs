@@ -50,12 +50,18 @@ fn test() {
 
             let mut toml = CargoToml::from_config(toml_contents, config).unwrap();
 
-            toml.format();
+            if let Err(e) = toml.format() {
+                println!("Failed to format file: {}. {e}", toml_path);
+                continue;
+            }
 
-            std::fs::write(
-                toml_path,
-                toml.toml_document.to_string().trim_end_matches("\r"),
-            );
+            for warning in &toml.warnings {
+                println!("Warning in file: {}. {warning}", toml_path);
+            }
+
+            if let Err(e) = std::fs::write(toml_path.clone(), toml.to_string()) {
+                println!("Failed to write file: {}. {e}", toml_path);
+            }
         } else {
             println!("Failed to read file: {}", toml_path);
         }
@@ -63,5 +69,5 @@ fn test() {
 }
 
 pub fn debug_table(table: &Table) {
-    println!("{}", table.to_string());
+    println!("{}", table);
 }
@@ -1,25 +1,34 @@
 use std::{
     cmp::Ordering,
     collections::{BTreeMap, HashMap, HashSet},
-    iter::FromIterator,
-    ops::Index,
     str::FromStr,
 };
 
-use strum::EnumProperty;
-use toml_edit::{ArrayOfTables, Decor, Document, Item, Key, Table, Value};
+use strum::{EnumProperty, VariantNames};
+use toml_edit::{ArrayOfTables, Document, Item, Key, Table, TableLike, Value};
 
 use crate::{
+    comments::{header_paths, split_leading_blank_lines, HeaderPath, SectionComments},
+    comparator::Collation,
+    directives::Directives,
     iter_sections_as_items, iter_sections_as_items_mut,
-    package_order::{PackageOrder, TomlSection, TomlSort},
+    package_order::{
+        DependencySection, FeatureSort, FeatureValueSort, PackageOrder, ProfileSection,
+        TargetOrder, TomlSection,
+    },
     toml_config::TomlFormatConfig,
-    utils::item_len,
+    utils::sort_array_by,
 };
 
 use super::TomlFormatter;
 
+/// See documentation on [crate::TomlFormatConfig::order_sections].
 ///
-pub struct OrderSections;
+/// The sections skipped by a [Directive](crate::directives::Directive) keep their place, the other sections are ordered around them.
+#[derive(Default)]
+pub struct OrderSections {
+    directives: Directives,
+}
 
 impl TomlFormatter for OrderSections {
     fn visit_document(
@@ -31,6 +40,9 @@ impl TomlFormatter for OrderSections {
             return Ok(());
         }
 
+        // The file header and the comments at the end of a section stay in place, instead of moving with the next section.
+        let section_comments = SectionComments::detach(toml_document);
+
         let mut section_tables = HashMap::<String, (Key, Table)>::new();
         let mut array_of_tables = HashMap::<String, (Key, ArrayOfTables)>::new();
 
@@ -50,6 +62,22 @@ impl TomlFormatter for OrderSections {
             sections_from_config.push(section);
         }
 
+        // The skipped sections keep their place among the sections as they are written.
+        let mut written_sections = Vec::<String>::new();
+        for path in header_paths(toml_document) {
+            if !written_sections.contains(&path[0].0) {
+                written_sections.push(path[0].0.clone());
+            }
+        }
+
+        let ordered_sections = sections_from_config
+            .into_iter()
+            .filter(|section| written_sections.contains(section))
+            .collect::<Vec<String>>();
+        let ordered_sections = pin(ordered_sections, &written_sections, |section| {
+            self.skips_section(toml_document.as_table(), &vec![], section)
+        });
+
         // Collect all section tables
         iter_sections_as_items(toml_document, |section_key, section_item| {
             if let Some(section_table) = section_item.as_table() {
@@ -72,13 +100,21 @@ impl TomlFormatter for OrderSections {
         let mut idx = 0;
 
         // Iterate tables as they should be ordered.
-        for ordered_section in sections_from_config {
+        for ordered_section in ordered_sections {
             if let Some((section_key, section_table)) = array_of_tables.get(&ordered_section) {
                 let mut new_tables = section_table.clone();
 
-                for table in new_tables.iter_mut() {
+                for (entry, table) in new_tables.iter_mut().enumerate() {
                     idx += 1;
                     table.set_position(idx);
+
+                    self.order_sub_tables(
+                        &[ordered_section.as_str()],
+                        &mut vec![(ordered_section.clone(), Some(entry))],
+                        table,
+                        &mut idx,
+                        &config.collation,
+                    );
                 }
 
                 toml_document.insert(section_key.get(), Item::ArrayOfTables(new_tables));
@@ -120,37 +156,19 @@ impl TomlFormatter for OrderSections {
                         panic!();
                     };
 
-                let section_has_pos = new_table.position().is_some();
-
-                // Iterate the sub tables and see if they need new indexes.
-                // TODO: add alphabetical sort.
-                new_table.iter_mut().for_each(|(_, recursive_item)| {
-                    // Only tables have positions.
-                    if let Some(table) = recursive_item.as_table_mut() {
-                        let subtable_has_pos = table.position().is_some();
-
-                        if (subtable_has_pos && section_has_pos)
-                            || (subtable_has_pos && !section_has_pos)
-                        {
-                            idx += 1;
-                            table.set_position(idx);
-                        } else if !subtable_has_pos && !section_has_pos {
-                            if !table.is_implicit() {
-                                // Both section and sub table have no positions.
-                                panic!("Not possible")
-                            } else {
-                                // handle sorting `[target.my-special-i686-platform.dependencies]` like tables.
-                            }
-                        } else if !subtable_has_pos && section_has_pos {
-                            // Sub table does not have any position.
-                        } else {
-                            panic!("Not possible")
-                        }
-                    }
-                });
+                // Iterate the sub tables and assign them new indexes in their sorted order.
+                self.order_sub_tables(
+                    &[ordered_section.as_str()],
+                    &mut vec![(ordered_section.clone(), None)],
+                    new_table,
+                    &mut idx,
+                    &config.collation,
+                );
             }
         }
 
+        section_comments.attach(toml_document);
+
         // if let Some(last) = toml_document.as_table_mut().iter_mut().last() {
         //     if let Item::Table(table) = last.1 {
         //         if let Some(last_item) =  table.iter_mut().last() {
@@ -166,6 +184,120 @@ impl TomlFormatter for OrderSections {
 
         Ok(())
     }
+
+    fn set_directives(&mut self, directives: &Directives) {
+        self.directives = directives.clone();
+    }
+}
+
+impl OrderSections {
+    /// Recursively assigns new positions to the sub tables of the given table.
+    ///
+    /// Sub tables are ordered by [OrderSections::compare_sub_tables].
+    /// Tables without a position, such as the implicit `target.'cfg(unix)'` in `[target.'cfg(unix)'.dependencies]`,
+    /// are not assigned an index but their sub tables are.
+    /// The header is the path of the table, including the index of an array of tables entry, to find the skipped sub tables.
+    fn order_sub_tables(
+        &self,
+        path: &[&str],
+        header: &mut HeaderPath,
+        table: &mut Table,
+        idx: &mut usize,
+        collation: &Collation,
+    ) {
+        let written_keys = table
+            .iter()
+            .filter(|(_, item)| !item.is_none() && !Directives::is_key(item))
+            .map(|(key, _)| key.to_string())
+            .collect::<Vec<String>>();
+
+        let mut sub_table_keys = written_keys.clone();
+        sub_table_keys
+            .sort_by(|key_1, key_2| Self::compare_sub_tables(path, key_1, key_2, collation));
+        let sub_table_keys = pin(sub_table_keys, &written_keys, |key| {
+            self.skips_section(table, header, key)
+        });
+
+        for sub_table_key in sub_table_keys {
+            let mut sub_path = path.to_vec();
+            sub_path.push(&sub_table_key);
+
+            match table.get_mut(&sub_table_key) {
+                Some(Item::Table(sub_table)) => {
+                    if sub_table.position().is_some() {
+                        *idx += 1;
+                        sub_table.set_position(*idx);
+                    }
+
+                    header.push((sub_table_key.clone(), None));
+                    self.order_sub_tables(&sub_path, header, sub_table, idx, collation);
+                    header.pop();
+                }
+                Some(Item::ArrayOfTables(sub_tables)) => {
+                    for (entry, sub_table) in sub_tables.iter_mut().enumerate() {
+                        *idx += 1;
+                        sub_table.set_position(*idx);
+
+                        header.push((sub_table_key.clone(), Some(entry)));
+                        self.order_sub_tables(&sub_path, header, sub_table, idx, collation);
+                        header.pop();
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Returns whether the section with the key in the table at the given header, or any entry of it, is skipped.
+    fn skips_section(&self, table: &Table, header: &HeaderPath, key: &str) -> bool {
+        let mut path = header.clone();
+        path.push((key.to_string(), None));
+
+        let entries = table
+            .get(key)
+            .and_then(Item::as_array_of_tables)
+            .map_or(0, ArrayOfTables::len);
+
+        self.directives.skips_header(&path)
+            || (0..entries).any(|entry| {
+                path.last_mut().unwrap().1 = Some(entry);
+                self.directives.skips_header(&path)
+            })
+    }
+
+    /// Compares two sub table keys of the table at the given path.
+    ///
+    /// - `[profile.*]`: `dev`, `release`, `test`, `bench`, followed by custom profiles alphabetically.
+    /// - `[target.*]`: targets alphabetically.
+    /// - `[target.*.*]`: dependency tables in [manifest][1] order.
+    /// - `[patch.*]`: `crates-io` first, followed by the other registries alphabetically.
+    /// - Any other sub table is ordered alphabetically.
+    ///
+    /// Alphabetical ordering uses the configured [Collation].
+    ///
+    /// [1]: https://doc.rust-lang.org/cargo/reference/manifest.html
+    fn compare_sub_tables(
+        path: &[&str],
+        key_1: &str,
+        key_2: &str,
+        collation: &Collation,
+    ) -> Ordering {
+        let rank = |key: &str| -> Option<usize> {
+            match path {
+                ["profile"] => ProfileSection::VARIANTS.iter().position(|p| *p == key),
+                ["target", _] => DependencySection::VARIANTS.iter().position(|d| *d == key),
+                ["patch"] => (key == "crates-io").then_some(0),
+                _ => None,
+            }
+        };
+
+        match (rank(key_1), rank(key_2)) {
+            (Some(rank_1), Some(rank_2)) => rank_1.cmp(&rank_2),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => collation.compare(key_1, key_2),
+        }
+    }
 }
 
 /// See documentation on [crate::TomlFormatConfig::order_package_section].
@@ -211,6 +343,359 @@ impl TomlFormatter for OrderPackageSection {
     }
 }
 
+/// See documentation on [crate::TomlFormatConfig::order_array_of_tables_by_name]
+/// and [crate::TomlFormatConfig::order_array_of_tables_keys].
+///
+/// The tables skipped by a [Directive](crate::directives::Directive) keep their place and the order of their keys.
+#[derive(Default)]
+pub struct OrderArrayOfTables {
+    directives: Directives,
+}
+
+impl TomlFormatter for OrderArrayOfTables {
+    fn visit_document(
+        &mut self,
+        toml_document: &mut Document,
+        config: &TomlFormatConfig,
+    ) -> anyhow::Result<()> {
+        for section in TARGET_SECTIONS {
+            if let Some(Item::ArrayOfTables(tables)) = toml_document.get_mut(section) {
+                let skipped = (0..tables.len())
+                    .map(|idx| {
+                        self.directives
+                            .skips_header(&vec![(section.to_string(), Some(idx))])
+                    })
+                    .collect::<Vec<bool>>();
+
+                if config.order_array_of_tables_keys {
+                    tables
+                        .iter_mut()
+                        .zip(&skipped)
+                        .filter(|(_, is_skipped)| !**is_skipped)
+                        .for_each(|(table, _)| Self::order_keys(table, &config.collation));
+                }
+
+                if config.order_array_of_tables_by_name {
+                    Self::order_by_name(tables, &skipped, &config.collation);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn set_directives(&mut self, directives: &Directives) {
+        self.directives = directives.clone();
+    }
+}
+
+/// Orders the items as sorted, except for the pinned items which are moved back to their index in the written order.
+fn pin<T: Clone + PartialEq>(
+    sorted: Vec<T>,
+    written: &[T],
+    is_pinned: impl Fn(&T) -> bool,
+) -> Vec<T> {
+    let mut ordered = sorted
+        .into_iter()
+        .filter(|item| !is_pinned(item))
+        .collect::<Vec<T>>();
+
+    for (idx, item) in written.iter().enumerate() {
+        if is_pinned(item) {
+            ordered.insert(idx.min(ordered.len()), item.clone());
+        }
+    }
+
+    ordered
+}
+
+/// The array of tables sections that describe a cargo target.
+const TARGET_SECTIONS: [&str; 4] = ["bin", "example", "test", "bench"];
+
+impl OrderArrayOfTables {
+    /// Sorts the tables by their `name` key, tables without a name are moved to the end.
+    ///
+    /// The comments above a `[[bin]]` header move with the table,
+    /// the blank lines before the headers stay where they were.
+    /// The skipped tables keep their index.
+    fn order_by_name(tables: &mut ArrayOfTables, skipped: &[bool], collation: &Collation) {
+        let mut positions = tables
+            .iter()
+            .filter_map(|table| table.position())
+            .collect::<Vec<usize>>();
+        positions.sort_unstable();
+
+        let blank_lines = tables
+            .iter()
+            .map(|table| {
+                table
+                    .decor()
+                    .prefix()
+                    .map(|prefix| split_leading_blank_lines(prefix).0.to_string())
+            })
+            .collect::<Vec<Option<String>>>();
+
+        let mut sorted_tables = (0..tables.len()).collect::<Vec<usize>>();
+        sorted_tables.sort_by(|idx_1, idx_2| {
+            let (table_1, table_2) = (tables.get(*idx_1).unwrap(), tables.get(*idx_2).unwrap());
+            let name_1 = table_1.get("name").and_then(|name| name.as_str());
+            let name_2 = table_2.get("name").and_then(|name| name.as_str());
+
+            match (name_1, name_2) {
+                (Some(name_1), Some(name_2)) => collation.compare(name_1, name_2),
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (None, None) => Ordering::Equal,
+            }
+        });
+        let sorted_tables = pin(
+            sorted_tables,
+            &(0..tables.len()).collect::<Vec<usize>>(),
+            |idx| skipped[*idx],
+        )
+        .into_iter()
+        .map(|idx| tables.get(idx).unwrap().clone())
+        .collect::<Vec<Table>>();
+
+        tables.clear();
+
+        // The document is rendered by table position, so the sorted tables take over the original positions.
+        for (idx, mut table) in sorted_tables.into_iter().enumerate() {
+            if let Some(position) = positions.get(idx) {
+                table.set_position(*position);
+            }
+
+            let comments = split_leading_blank_lines(table.decor().prefix().unwrap_or_default())
+                .1
+                .to_string();
+            match (&blank_lines[idx], comments.is_empty()) {
+                (Some(blank_lines), _) => table
+                    .decor_mut()
+                    .set_prefix(format!("{blank_lines}{comments}")),
+                (None, false) => table.decor_mut().set_prefix(comments),
+                // Without a prefix of its own, the header is written with the default blank lines.
+                (None, true) => {
+                    let suffix = table.decor().suffix().map(str::to_string);
+                    table.decor_mut().clear();
+                    if let Some(suffix) = suffix {
+                        table.decor_mut().set_suffix(suffix);
+                    }
+                }
+            }
+
+            tables.push(table);
+        }
+    }
+
+    /// Orders the keys of a target table according to [TargetOrder], unknown keys are ordered alphabetically after.
+    fn order_keys(table: &mut Table, collation: &Collation) {
+        table.sort_values_by(|key_1, _, key_2, _| {
+            match (
+                TargetOrder::order_of(key_1.get()),
+                TargetOrder::order_of(key_2.get()),
+            ) {
+                (Some(order_1), Some(order_2)) => order_1.cmp(&order_2),
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (None, None) => collation.compare(key_1.get(), key_2.get()),
+            }
+        });
+    }
+}
+
+/// See documentation on [crate::TomlFormatConfig::order_features]
+/// and [crate::TomlFormatConfig::order_feature_values].
+pub struct OrderFeatures;
+
+impl TomlFormatter for OrderFeatures {
+    fn visit_document(
+        &mut self,
+        toml_document: &mut Document,
+        config: &TomlFormatConfig,
+    ) -> anyhow::Result<()> {
+        if let Some(Item::Table(features)) = toml_document.get_mut("features") {
+            if let Some(feature_sort) = config.order_features {
+                Self::order_features(features, feature_sort, &config.collation);
+            }
+
+            if let Some(feature_value_sort) = config.order_feature_values {
+                features.iter_mut().for_each(|(_, item)| {
+                    if let Some(Value::Array(values)) = item.as_value_mut() {
+                        sort_array_by(values, false, |value_1, value_2| {
+                            Self::compare_feature_values(
+                                value_1,
+                                value_2,
+                                feature_value_sort,
+                                &config.collation,
+                            )
+                        });
+                    }
+                });
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl OrderFeatures {
+    /// Orders the features with `default` pinned first.
+    fn order_features(features: &mut Table, feature_sort: FeatureSort, collation: &Collation) {
+        let mut feature_names = features
+            .iter()
+            .map(|(key, _)| key.to_string())
+            .collect::<Vec<String>>();
+        feature_names.sort_by(|name_1, name_2| collation.compare(name_1, name_2));
+
+        if feature_sort == FeatureSort::Dependency {
+            let mut ordered = Vec::with_capacity(feature_names.len());
+            let mut visited = HashSet::new();
+
+            for feature_name in &feature_names {
+                Self::visit_feature(
+                    features,
+                    feature_name,
+                    &mut visited,
+                    &mut ordered,
+                    collation,
+                );
+            }
+
+            feature_names = ordered;
+        }
+
+        features.sort_values_by(|key_1, _, key_2, _| {
+            let rank = |key: &Key| {
+                let position = feature_names.iter().position(|name| name == key.get());
+                (key.get() != "default", position)
+            };
+
+            rank(key_1).cmp(&rank(key_2))
+        });
+    }
+
+    /// Depth first visit of the local features enabled by the given feature, adding the prerequisites before the feature itself.
+    fn visit_feature(
+        features: &Table,
+        feature_name: &str,
+        visited: &mut HashSet<String>,
+        ordered: &mut Vec<String>,
+        collation: &Collation,
+    ) {
+        if !visited.insert(feature_name.to_string()) {
+            return;
+        }
+
+        if let Some(Value::Array(values)) = features.get(feature_name).and_then(Item::as_value) {
+            let mut prerequisites = values
+                .iter()
+                .filter_map(Value::as_str)
+                .filter(|value| features.contains_key(value) && !value.contains('/'))
+                .collect::<Vec<&str>>();
+            prerequisites.sort_by(|prerequisite_1, prerequisite_2| {
+                collation.compare(prerequisite_1, prerequisite_2)
+            });
+
+            for prerequisite in prerequisites {
+                Self::visit_feature(features, prerequisite, visited, ordered, collation);
+            }
+        }
+
+        ordered.push(feature_name.to_string());
+    }
+
+    fn compare_feature_values(
+        value_1: &Value,
+        value_2: &Value,
+        feature_value_sort: FeatureValueSort,
+        collation: &Collation,
+    ) -> Ordering {
+        let (value_1, value_2) = match (value_1.as_str(), value_2.as_str()) {
+            (Some(value_1), Some(value_2)) => (value_1, value_2),
+            _ => return Ordering::Equal,
+        };
+
+        let group = |value: &str| match feature_value_sort {
+            FeatureValueSort::Alphabetical => 0,
+            FeatureValueSort::Grouped if value.starts_with("dep:") => 0,
+            FeatureValueSort::Grouped if value.contains('/') => 1,
+            FeatureValueSort::Grouped => 2,
+        };
+
+        group(value_1)
+            .cmp(&group(value_2))
+            .then_with(|| collation.compare(value_1, value_2))
+    }
+}
+
+/// See documentation on [crate::TomlFormatConfig::order_array_values].
+pub struct OrderArrayValues;
+
+impl TomlFormatter for OrderArrayValues {
+    fn visit_document(
+        &mut self,
+        toml_document: &mut Document,
+        config: &TomlFormatConfig,
+    ) -> anyhow::Result<()> {
+        if config.order_array_values.is_empty() {
+            return Ok(());
+        }
+
+        Self::visit_table(toml_document.as_table_mut(), &mut vec![], config);
+
+        Ok(())
+    }
+}
+
+impl OrderArrayValues {
+    fn visit_table(table: &mut dyn TableLike, path: &mut Vec<String>, config: &TomlFormatConfig) {
+        table.iter_mut().for_each(|(key, item)| {
+            path.push(key.get().to_string());
+            Self::visit_item(item, path, config);
+            path.pop();
+        });
+    }
+
+    fn visit_item(item: &mut Item, path: &mut Vec<String>, config: &TomlFormatConfig) {
+        match item {
+            Item::Value(Value::Array(array)) => {
+                let is_configured = config
+                    .order_array_values
+                    .iter()
+                    .any(|pattern| Self::path_matches(pattern, path));
+
+                // Only arrays of strings are sorted, mixed arrays are left as they are.
+                if is_configured && array.iter().all(Value::is_str) {
+                    sort_array_by(array, true, |value_1, value_2| {
+                        config
+                            .collation
+                            .compare(value_1.as_str().unwrap(), value_2.as_str().unwrap())
+                    });
+                }
+            }
+            Item::Value(Value::InlineTable(inline_table)) => {
+                Self::visit_table(inline_table, path, config)
+            }
+            Item::Table(table) => Self::visit_table(table, path, config),
+            Item::ArrayOfTables(tables) => tables
+                .iter_mut()
+                .for_each(|table| Self::visit_table(table, path, config)),
+            Item::Value(_) | Item::None => {}
+        }
+    }
+
+    /// Returns whether the dotted pattern, e.g. `features.*`, matches the key path. A `*` matches any single key.
+    fn path_matches(pattern: &str, path: &[String]) -> bool {
+        let segments = pattern.split('.').collect::<Vec<&str>>();
+
+        segments.len() == path.len()
+            && segments
+                .iter()
+                .zip(path)
+                .all(|(segment, key)| *segment == "*" || segment == key)
+    }
+}
+
 /// Order all table and inline table keys alphabetical order.
 pub struct OrderTableKeysAlphabetically;
 
@@ -238,7 +723,7 @@ impl OrderTableKeysAlphabetically {
     pub fn order_item(item: &mut Item, config: &TomlFormatConfig) {
         match item {
             Item::None => todo!(),
-            Item::Value(value) => Self::order_value(value),
+            Item::Value(value) => Self::order_value(value, config),
             Item::Table(table) => Self::order_table(table, config),
             Item::ArrayOfTables(tables) => tables.iter_mut().for_each(|table| {
                 Self::order_table(table, config);
@@ -247,7 +732,7 @@ impl OrderTableKeysAlphabetically {
     }
 
     pub fn order_table(table: &mut Table, config: &TomlFormatConfig) {
-        table.sort_values_by(|key_1, val1, key_2, val2| {
+        table.sort_values_by(|key_1, _val1, key_2, _val2| {
             if config
                 .exclude_keys_from_ordering
                 .iter()
@@ -255,7 +740,7 @@ impl OrderTableKeysAlphabetically {
             {
                 Ordering::Equal
             } else {
-                key_1.get().cmp(&key_2.get())
+                config.collation.compare(key_1.get(), key_2.get())
             }
         });
 
@@ -264,16 +749,18 @@ impl OrderTableKeysAlphabetically {
         })
     }
 
-    pub fn order_value(value: &mut toml_edit::Value) {
+    pub fn order_value(value: &mut toml_edit::Value, config: &TomlFormatConfig) {
         match value {
             Value::Array(array) => array.iter_mut().for_each(|value| {
-                Self::order_value(value);
+                Self::order_value(value, config);
             }),
             Value::InlineTable(inline_table) => {
-                inline_table.sort_values_by(|key_1, _, key_2, _| key_1.get().cmp(&key_2.get()));
+                inline_table.sort_values_by(|key_1, _, key_2, _| {
+                    config.collation.compare(key_1.get(), key_2.get())
+                });
 
                 inline_table.iter_mut().for_each(|(_, value)| {
-                    Self::order_value(value);
+                    Self::order_value(value, config);
                 })
             }
             Value::String(_)
@@ -314,17 +801,8 @@ impl OrderDependencies {
         config: &TomlFormatConfig,
     ) -> anyhow::Result<()> {
         if let Item::Table(ref mut dependencies) = dependencies {
-            fn alphabetical_sort(
-                key_1: &Key,
-                _: &Item,
-                key_2: &Key,
-                _: &Item,
-            ) -> std::cmp::Ordering {
-                key_1.get().to_lowercase().cmp(&key_2.get().to_lowercase())
-            }
-
-            dependencies.sort_values_by(|key_1, _item_1, key_2, _item_2| {
-                alphabetical_sort(key_1, _item_1, key_2, _item_2)
+            dependencies.sort_values_by(|key_1, _, key_2, _| {
+                config.collation.compare(key_1.get(), key_2.get())
             });
         }
 
@@ -365,7 +843,7 @@ impl TomlFormatter for OrderSectionKeysByGroupAlphabetically {
                     .enumerate()
                 {
                     let table_key_decor = table_key.decor_mut();
-                    
+
                     // A group exists if there is at least one blank line between two keys.
 
                     let blank_lines = table_key_decor
@@ -373,35 +851,41 @@ impl TomlFormatter for OrderSectionKeysByGroupAlphabetically {
                         .map(|prefix| prefix.lines().filter(|l| !l.starts_with('#')).count())
                         .unwrap_or(0);
 
-                    if blank_lines > 0 {                     
+                    if blank_lines > 0 {
                         // We may need the the original decor for the top sorted item of the group.
                         group_header.insert(idx, table_key_decor.clone());
-                        
-                           // Reset the prefix since a new entry of the group may be sorted to the top.
-                           table_key_decor.set_prefix("");
 
-                        groups.entry(idx).or_insert_with(|| vec![(table_key, table_value)]);
+                        // Reset the prefix since a new entry of the group may be sorted to the top.
+                        table_key_decor.set_prefix("");
+
+                        groups
+                            .entry(idx)
+                            .or_insert_with(|| vec![(table_key, table_value)]);
                         group_idx = idx;
-                    } else {                    
+                    } else {
                         if !groups.contains_key(&group_idx) {
                             group_header.insert(group_idx, table_key_decor.clone());
                         }
 
-                        groups.entry(group_idx).or_default().push((table_key, table_value));
+                        groups
+                            .entry(group_idx)
+                            .or_default()
+                            .push((table_key, table_value));
                     }
                 }
 
                 for (idx, group) in groups.iter_mut() {
-                    group.sort_by(|a, b| a.0.cmp(&b.0));
+                    group.sort_by(|a, b| config.collation.compare(a.0.get(), b.0.get()));
 
                     for (i, (key, value)) in group.iter_mut().enumerate() {
                         // Only apply the original group header to the first item of the group.
                         if i == 0 {
                             let original_decor = group_header.get(idx).unwrap();
-                            key.decor_mut().set_prefix(original_decor.prefix().unwrap_or(""));
+                            key.decor_mut()
+                                .set_prefix(original_decor.prefix().unwrap_or(""));
                         }
 
-                        table.insert_formatted(&key, value.clone());
+                        table.insert_formatted(key, value.clone());
                     }
                 }
             }
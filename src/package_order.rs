@@ -1,3 +1,5 @@
+use std::str::FromStr;
+
 use serde::{Deserialize, Serialize};
 use strum::{AsRefStr, Display, EnumProperty, EnumString, EnumVariantNames, VariantNames};
 
@@ -19,6 +21,50 @@ pub enum TomlSort {
     Length,
 }
 
+/// The order of the features in the `[features]` section, `default` is always ordered first.
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    Display,
+    AsRefStr,
+    Eq,
+    PartialEq,
+    PartialOrd,
+    Hash,
+    EnumString,
+    Serialize,
+    Deserialize,
+)]
+pub enum FeatureSort {
+    /// Features are ordered alphabetically.
+    Alphabetical,
+    /// Features are ordered after the local features they enable, ties are ordered alphabetically.
+    Dependency,
+}
+
+/// The order of the values within a feature its array.
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    Display,
+    AsRefStr,
+    Eq,
+    PartialEq,
+    PartialOrd,
+    Hash,
+    EnumString,
+    Serialize,
+    Deserialize,
+)]
+pub enum FeatureValueSort {
+    /// Values are ordered alphabetically.
+    Alphabetical,
+    /// `dep:` values first, then `crate/feature` values, then local features. Each group is ordered alphabetically.
+    Grouped,
+}
+
 #[derive(
     Clone,
     Debug,
@@ -80,6 +126,57 @@ impl TomlSection {
     }
 }
 
+/// The order of the profiles in the `[profile]` section.
+/// Custom profiles are ordered after these.
+#[derive(
+    Clone,
+    Debug,
+    Display,
+    AsRefStr,
+    Eq,
+    PartialEq,
+    PartialOrd,
+    Hash,
+    EnumString,
+    Serialize,
+    Deserialize,
+    EnumVariantNames,
+)]
+pub enum ProfileSection {
+    #[strum(serialize = "dev")]
+    Dev,
+    #[strum(serialize = "release")]
+    Release,
+    #[strum(serialize = "test")]
+    Test,
+    #[strum(serialize = "bench")]
+    Bench,
+}
+
+/// The order of the dependency tables, e.g. within a `[target.*]` section.
+#[derive(
+    Clone,
+    Debug,
+    Display,
+    AsRefStr,
+    Eq,
+    PartialEq,
+    PartialOrd,
+    Hash,
+    EnumString,
+    Serialize,
+    Deserialize,
+    EnumVariantNames,
+)]
+pub enum DependencySection {
+    #[strum(serialize = "dependencies")]
+    Dependencies,
+    #[strum(serialize = "dev-dependencies")]
+    DevDependencies,
+    #[strum(serialize = "build-dependencies")]
+    BuildDependencies,
+}
+
 #[derive(
     Clone,
     Debug,
@@ -160,3 +257,66 @@ impl PackageOrder {
             .collect()
     }
 }
+
+/// The order of the keys within the `[[bin]]`, `[[example]]`, `[[test]]` and `[[bench]]` target sections.
+#[derive(
+    Clone,
+    Debug,
+    Display,
+    EnumVariantNames,
+    AsRefStr,
+    Eq,
+    PartialEq,
+    PartialOrd,
+    Hash,
+    EnumString,
+    Serialize,
+    Deserialize,
+    EnumProperty,
+)]
+pub enum TargetOrder {
+    #[strum(serialize = "name", props(order = "0"))]
+    Name,
+    #[strum(serialize = "path", props(order = "1"))]
+    Path,
+    #[strum(serialize = "required-features", props(order = "2"))]
+    RequiredFeatures,
+    #[strum(serialize = "test", props(order = "3"))]
+    Test,
+    #[strum(serialize = "doctest", props(order = "4"))]
+    Doctest,
+    #[strum(serialize = "bench", props(order = "5"))]
+    Bench,
+    #[strum(serialize = "doc", props(order = "6"))]
+    Doc,
+    #[strum(serialize = "plugin", props(order = "7"))]
+    Plugin,
+    #[strum(serialize = "proc-macro", props(order = "8"))]
+    ProcMacro,
+    #[strum(serialize = "harness", props(order = "9"))]
+    Harness,
+    #[strum(serialize = "edition", props(order = "10"))]
+    Edition,
+    #[strum(serialize = "crate-type", props(order = "11"))]
+    CrateType,
+}
+
+impl TargetOrder {
+    pub fn manifest_spec() -> Vec<String> {
+        TargetOrder::VARIANTS
+            .iter()
+            .map(|f| f.to_string())
+            .collect()
+    }
+
+    /// Returns the position of the given key, `None` if the key is not a known target key.
+    pub fn order_of(key: &str) -> Option<u8> {
+        TargetOrder::from_str(key).ok().map(|order| {
+            order
+                .get_str("order")
+                .expect("order should be defined in enum")
+                .parse::<u8>()
+                .expect("order should be a u8 integer")
+        })
+    }
+}
@@ -1,6 +1,9 @@
 use serde::{Deserialize, Serialize};
+use strum::{AsRefStr, Display, EnumString};
 
-use crate::package_order::{TomlSection, TomlSort};
+use crate::comparator::Collation;
+use crate::package_order::{FeatureSort, FeatureValueSort, TomlSection};
+use crate::version::VersionFormat;
 
 #[derive(Clone, Debug, Eq, PartialEq, PartialOrd, Hash, Serialize, Deserialize)]
 pub struct TomlFormatConfig {
@@ -75,6 +78,113 @@ pub struct TomlFormatConfig {
     /// [1]: https://doc.rust-lang.org/cargo/reference/manifest.html
     pub order_package_section: bool,
 
+    /// Order the `[[bin]]`, `[[example]]`, `[[test]]` and `[[bench]]` entries by their `name` key.
+    /// Comments above an entry's header move along with the entry.
+    ///
+    /// ```toml
+    /// [[bin]]
+    /// name = "b"
+    ///
+    /// # The first binary.
+    /// [[bin]]
+    /// name = "a"
+    /// ```
+    ///
+    /// TO:
+    ///
+    /// ```toml
+    /// # The first binary.
+    /// [[bin]]
+    /// name = "a"
+    ///
+    /// [[bin]]
+    /// name = "b"
+    /// ```
+    pub order_array_of_tables_by_name: bool,
+
+    /// Order the keys within the `[[bin]]`, `[[example]]`, `[[test]]` and `[[bench]]` entries
+    /// as `name`, `path`, `required-features`, followed by the remaining [target][1] keys.
+    ///
+    /// ```toml
+    /// [[bin]]
+    /// test = false
+    /// path = "src/main.rs"
+    /// name = "cool-tool"
+    /// ```
+    ///
+    /// TO:
+    ///
+    /// ```toml
+    /// [[bin]]
+    /// name = "cool-tool"
+    /// path = "src/main.rs"
+    /// test = false
+    /// ```
+    ///
+    /// [1]: https://doc.rust-lang.org/cargo/reference/cargo-targets.html#configuring-a-target
+    pub order_array_of_tables_keys: bool,
+
+    /// Order the `[features]` section with the `default` feature pinned first.
+    /// The remaining features are ordered according to the given [FeatureSort].
+    ///
+    /// ```toml
+    /// [features]
+    /// c = ["a"]
+    /// a = ["b"]
+    /// default = ["c"]
+    /// b = []
+    /// ```
+    ///
+    /// TO (with [FeatureSort::Dependency]):
+    ///
+    /// ```toml
+    /// [features]
+    /// default = ["c"]
+    /// b = []
+    /// a = ["b"]
+    /// c = ["a"]
+    /// ```
+    pub order_features: Option<FeatureSort>,
+
+    /// Order the values of each feature according to the given [FeatureValueSort].
+    ///
+    /// ```toml
+    /// [features]
+    /// a = ["b", "serde/std", "dep:serde"]
+    /// ```
+    ///
+    /// TO (with [FeatureValueSort::Grouped]):
+    ///
+    /// ```toml
+    /// [features]
+    /// a = ["dep:serde", "serde/std", "b"]
+    /// ```
+    pub order_feature_values: Option<FeatureValueSort>,
+
+    /// Order and deduplicate the string values of the arrays at the given key paths.
+    /// A path is a dotted key where `*` matches any key, e.g. `workspace.members`, `package.keywords` or `features.*`.
+    /// Trailing comments stay attached to the value they describe.
+    ///
+    /// ```toml
+    /// [workspace]
+    /// members = [
+    ///     "b", # The b crate.
+    ///     "a",
+    ///     "b",
+    /// ]
+    /// ```
+    ///
+    /// TO (with `workspace.members`):
+    ///
+    /// ```toml
+    /// [workspace]
+    /// members = [
+    ///     "a",
+    ///     "b", # The b crate.
+    /// ]
+    /// ```
+    pub order_array_values: Vec<String>,
+
     //// Order table keys alphabetically.
     ///
     /// ```toml
@@ -93,19 +203,19 @@ pub struct TomlFormatConfig {
     /// c = "c"
     /// ```
     pub order_table_keys_alphabetically: bool,
-    /// Order the section keys, except from package, by group then alphabetically. 
-    /// A group is defined by a white space after an table item. Comments are skipped when counting whitespaces. 
-    /// 
+    /// Order the section keys, except from package, by group then alphabetically.
+    /// A group is defined by a white space after an table item. Comments are skipped when counting whitespaces.
+    ///
     /// ```toml
-    /// [some-table] 
+    /// [some-table]
     /// c = "b"         # <-- group 1 start
     /// a = "c"
     ///                 # <-- group 2 start
     /// d = "a"
     /// b = "a"
-    /// 
+    ///
     /// # Section 3     # <-- group 3 start
-    /// 
+    ///
     /// f = "a"
     /// e = "a"
     /// ```
@@ -115,12 +225,12 @@ pub struct TomlFormatConfig {
     /// [some-table]
     /// a = "a"
     /// c = "a"
-    /// 
+    ///
     /// b = "a"
     /// d = "a"
-    /// 
+    ///
     /// # Section 2
-    /// 
+    ///
     /// e = "a"
     /// f = "a"
     /// ```
@@ -130,6 +240,23 @@ pub struct TomlFormatConfig {
     /// When [TomlFormatConfig::order_table_keys_alphabetically] is enabled, exclude certain tables from their **items** being reordered.
     pub exclude_tables_from_ordering: Vec<String>,
 
+    /// The collation used by all ordering rules when keys or values are compared, [Collation::byte] by default.
+    ///
+    /// ```toml
+    /// [dependencies]
+    /// foo-10 = "1"
+    /// Foo-2 = "1"
+    /// ```
+    ///
+    /// TO (with a case-insensitive natural [Collation]):
+    ///
+    /// ```toml
+    /// [dependencies]
+    /// Foo-2 = "1"
+    /// foo-10 = "1"
+    /// ```
+    pub collation: Collation,
+
     /// Trims empty spaces around the section names.
     ///
     /// ```toml
@@ -179,6 +306,10 @@ pub struct TomlFormatConfig {
     /// ```
     pub trim_quotes_table_keys: bool,
 
+    /// The quotes of keys that can't be written bare when [TomlFormatConfig::trim_quotes_table_keys] is enabled,
+    /// e.g. `[target.'cfg(unix)'.dependencies]` with [QuoteStyle::Single].
+    pub key_quote_style: QuoteStyle,
+
     pub add_newline_after_section: bool,
     /// Formats all tables in the toml document.
     ///
@@ -190,6 +321,154 @@ pub struct TomlFormatConfig {
     pub table_formatting: bool,
     pub wrap_array: Option<usize>,
     pub wrap_table: Option<usize>,
+
+    /// Indents the keys of sub-tables, e.g. `[dependencies.a]` or `[profile.release]`, and of arrays of tables, e.g. `[[bin]]`.
+    /// See [crate::formatting::TableIndentation].
+    ///
+    /// ```toml
+    /// [[bin]]
+    ///     name = "a"
+    ///     path = "src/a.rs"
+    /// ```
+    ///
+    /// Keys are not indented by default, as recommended by the Cargo.toml style guide.
+    /// Wrapped arrays are indented relative to their key.
+    pub table_indent: Option<Indent>,
+
+    /// Exclude certain sections from [TomlFormatConfig::wrap_array], e.g. `package`.
+    pub exclude_tables_from_wrapping: Vec<String>,
+
+    /// The indentation of the values of a wrapped array.
+    pub wrap_indent: Indent,
+
+    /// Adds a comma after the last value of a wrapped array.
+    ///
+    /// ```toml
+    /// a = [
+    ///     "a",
+    ///     "b",
+    /// ]
+    /// ```
+    pub wrap_trailing_comma: bool,
+
+    /// The way the values of a wrapped array are spread over the lines, see [ArrayPacking].
+    pub wrap_packing: ArrayPacking,
+
+    /// Collapses dependency tables, e.g. `[dependencies.a]`, into an inline table when it fits within [TomlFormatConfig::wrap_table].
+    /// See [crate::formatting::InlineTableCollapse].
+    pub collapse_tables: bool,
+
+    /// Wraps the `package.description` at the given width when its line is longer than the width.
+    /// See [crate::formatting::WrapDescription].
+    pub wrap_description: Option<usize>,
+
+    /// Rewrites strings containing newlines, e.g. `"a\nb"`, into multi-line strings (`"""`).
+    /// See [crate::formatting::MultilineStrings].
+    pub use_multiline_strings: bool,
+
+    /// When [TomlFormatConfig::use_multiline_strings] is enabled, prefer a literal multi-line string (`'''`)
+    /// when the string doesn't need any escaping.
+    pub prefer_literal_multiline_strings: bool,
+
+    /// Normalizes the quotes of single-line strings, see [QuoteStyle].
+    pub string_quote_style: Option<QuoteStyle>,
+
+    /// Normalizes dependencies to the configured [DependencyStyle] in all dependency tables.
+    /// See [crate::formatting::DependencyShorthand].
+    ///
+    /// ```toml
+    /// [dependencies]
+    /// a = { version = "0.1" }
+    /// ```
+    ///
+    /// TO (with [DependencyStyle::Short]):
+    ///
+    /// ```toml
+    /// [dependencies]
+    /// a = "0.1"
+    /// ```
+    pub dependency_style: Option<DependencyStyle>,
+
+    /// Writes dependencies inherited from the workspace as a dotted key.
+    ///
+    /// ```toml
+    /// [dependencies]
+    /// a = { workspace = true }
+    /// ```
+    ///
+    /// TO:
+    ///
+    /// ```toml
+    /// [dependencies]
+    /// a.workspace = true
+    /// ```
+    pub dotted_workspace_dependencies: bool,
+
+    /// Expands or collapses dotted keys, e.g. `a.workspace = true`, see [DottedKeyStyle].
+    /// See [crate::formatting::DottedKeyFormatting].
+    ///
+    /// [TomlFormatConfig::dotted_workspace_dependencies] takes precedence for `{ workspace = true }` dependencies.
+    pub dotted_keys: Option<DottedKeyStyle>,
+
+    /// Rewrites the `version` requirement of dependencies to the canonical [VersionFormat].
+    /// See [crate::formatting::VersionRequirementFormatting].
+    ///
+    /// ```toml
+    /// [dependencies]
+    /// a = "^1"
+    /// b = { version = "1.0.0" }
+    /// ```
+    ///
+    /// TO (with 2 components):
+    ///
+    /// ```toml
+    /// [dependencies]
+    /// a = "1.0"
+    /// b = { version = "1.0" }
+    /// ```
+    pub version_format: Option<VersionFormat>,
+
+    /// Warns about dependencies with a wildcard version requirement, e.g. `a = "*"`.
+    pub lint_wildcard_versions: bool,
+
+    /// Sets the number of blank lines between keys, comments and sections, see [BlankLines].
+    /// See [crate::formatting::BlankLineFormatting].
+    ///
+    /// The blank lines between keys are kept by [TomlFormatConfig::table_formatting] and [TomlFormatConfig::trim_section_item_keys]
+    /// when a policy is set, so groups of keys survive.
+    pub blank_lines: Option<BlankLines>,
+
+    /// Aligns the `=` of the keys within each group of keys, groups are separated by blank lines.
+    /// See [crate::formatting::AlignEntries].
+    ///
+    /// ```toml
+    /// [dependencies]
+    /// a = "0.1"
+    /// serde = "1.0"
+    /// ```
+    ///
+    /// TO:
+    ///
+    /// ```toml
+    /// [dependencies]
+    /// a     = "0.1"
+    /// serde = "1.0"
+    /// ```
+    ///
+    /// Lines that would become longer than [TomlFormatConfig::wrap_array] are not aligned.
+    pub align_keys: bool,
+
+    /// Aligns the trailing comments within each group of keys to a common column, like [TomlFormatConfig::align_keys].
+    pub align_comments: bool,
+
+    /// The line endings of the formatted file, see [LineEnding].
+    pub line_ending: LineEnding,
+
+    /// Ends the formatted file with exactly one newline, removing trailing blank lines and whitespace.
+    pub final_newline: bool,
+
+    /// Removes the byte order mark at the start of the file, otherwise a byte order mark is kept when the file has one.
+    pub strip_bom: bool,
 }
 
 impl TomlFormatConfig {
@@ -201,16 +480,44 @@ impl TomlFormatConfig {
             custom_section_order: vec![],
             order_dependencies_alphabetically: false,
             order_package_section: false,
+            order_array_of_tables_by_name: false,
+            order_array_of_tables_keys: false,
+            order_features: None,
+            order_feature_values: None,
+            order_array_values: vec![],
             order_table_keys_alphabetically: false,
             exclude_keys_from_ordering: vec![],
             exclude_tables_from_ordering: vec![],
+            collation: Collation::byte(),
             trim_section_key_names: false,
             trim_section_item_keys: false,
             add_newline_after_section: false,
             trim_quotes_table_keys: false,
+            key_quote_style: QuoteStyle::Double,
             table_formatting: false,
             wrap_array: None,
             wrap_table: None,
+            table_indent: None,
+            exclude_tables_from_wrapping: vec![],
+            wrap_indent: Indent::Spaces(4),
+            wrap_trailing_comma: false,
+            wrap_packing: ArrayPacking::OnePerLine,
+            collapse_tables: false,
+            wrap_description: None,
+            use_multiline_strings: false,
+            prefer_literal_multiline_strings: false,
+            string_quote_style: None,
+            dependency_style: None,
+            dotted_workspace_dependencies: false,
+            dotted_keys: None,
+            version_format: None,
+            lint_wildcard_versions: false,
+            blank_lines: None,
+            align_keys: false,
+            align_comments: false,
+            line_ending: LineEnding::Preserve,
+            final_newline: false,
+            strip_bom: false,
             order_section_keys_by_group_alphabetically: false,
         }
     }
@@ -224,17 +531,243 @@ impl Default for TomlFormatConfig {
             custom_section_order: TomlSection::manifest_spec(),
             order_dependencies_alphabetically: true,
             order_package_section: true,
+            order_array_of_tables_by_name: false,
+            order_array_of_tables_keys: false,
+            order_features: None,
+            order_feature_values: None,
+            order_array_values: vec![],
             order_table_keys_alphabetically: true,
             exclude_keys_from_ordering: vec![],
             exclude_tables_from_ordering: vec!["package".to_string()],
+            collation: Collation::byte(),
             trim_section_key_names: true,
             trim_section_item_keys: true,
             trim_quotes_table_keys: true,
+            key_quote_style: QuoteStyle::Single,
             add_newline_after_section: true,
             table_formatting: true,
             wrap_array: Some(50),
             wrap_table: Some(50),
+            table_indent: None,
+            exclude_tables_from_wrapping: vec!["package".to_string()],
+            wrap_indent: Indent::Spaces(4),
+            wrap_trailing_comma: true,
+            wrap_packing: ArrayPacking::OnePerLine,
+            collapse_tables: false,
+            wrap_description: None,
+            use_multiline_strings: false,
+            prefer_literal_multiline_strings: false,
+            string_quote_style: None,
+            dependency_style: None,
+            dotted_workspace_dependencies: false,
+            dotted_keys: None,
+            version_format: None,
+            lint_wildcard_versions: false,
+            blank_lines: None,
+            align_keys: false,
+            align_comments: false,
+            line_ending: LineEnding::Preserve,
+            final_newline: true,
+            strip_bom: false,
             order_section_keys_by_group_alphabetically: false,
         }
     }
 }
+
+/// The preferred quotes of single-line strings.
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    Display,
+    AsRefStr,
+    Eq,
+    PartialEq,
+    PartialOrd,
+    Hash,
+    EnumString,
+    Serialize,
+    Deserialize,
+)]
+pub enum QuoteStyle {
+    /// Basic strings (`"..."`), literal strings (`'...'`) are only used when the basic string would need escaping,
+    /// e.g. Windows paths or regexes.
+    Double,
+    /// Literal strings (`'...'`), basic strings are only used when the string can't be written as a literal string.
+    Single,
+}
+
+/// The way a dependency with only a version requirement is written.
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    Display,
+    AsRefStr,
+    Eq,
+    PartialEq,
+    PartialOrd,
+    Hash,
+    EnumString,
+    Serialize,
+    Deserialize,
+)]
+pub enum DependencyStyle {
+    /// `a = "0.1"`, an inline table with only a `version` is written as the version itself.
+    Short,
+    /// `a = { version = "0.1" }`, so all dependencies are written the same way.
+    Table,
+}
+
+/// The way dotted keys are written.
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    Display,
+    AsRefStr,
+    Eq,
+    PartialEq,
+    PartialOrd,
+    Hash,
+    EnumString,
+    Serialize,
+    Deserialize,
+)]
+pub enum DottedKeyStyle {
+    /// Dotted keys are written as sub-tables, e.g. `[package.metadata.docs.rs]`,
+    /// or as inline tables within dependency tables and inline tables, e.g. `a = { workspace = true }`.
+    Expand,
+    /// Sub-tables and nested inline tables with a single key are written as a dotted key, e.g. `a.workspace = true`.
+    Collapse,
+}
+
+/// The indentation of wrapped lines.
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    Display,
+    AsRefStr,
+    Eq,
+    PartialEq,
+    PartialOrd,
+    Hash,
+    EnumString,
+    Serialize,
+    Deserialize,
+)]
+pub enum Indent {
+    /// Indents with the given number of spaces.
+    Spaces(usize),
+    /// Indents with a single tab.
+    Tab,
+}
+
+impl Indent {
+    /// The width of a tab when measuring the length of a line.
+    pub const TAB_WIDTH: usize = 4;
+
+    /// Returns the whitespace of a single level of indentation.
+    pub fn as_string(&self) -> String {
+        match self {
+            Indent::Spaces(width) => " ".repeat(*width),
+            Indent::Tab => "\t".to_string(),
+        }
+    }
+}
+
+/// The way the values of a wrapped array are spread over the lines.
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    Display,
+    AsRefStr,
+    Eq,
+    PartialEq,
+    PartialOrd,
+    Hash,
+    EnumString,
+    Serialize,
+    Deserialize,
+)]
+pub enum ArrayPacking {
+    /// Each value is written on its own line.
+    OnePerLine,
+    /// As many values as fit within the wrap width are written on each line.
+    ///
+    /// Arrays containing comments are always written with one value per line.
+    Fill,
+}
+
+/// The number of blank lines between keys, comments and sections.
+///
+/// There are never blank lines between a section header and its first key.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, PartialOrd, Hash, Serialize, Deserialize)]
+pub struct BlankLines {
+    /// The maximum number of consecutive blank lines within a table, e.g. between groups of keys.
+    ///
+    /// With 0 a single blank line is still kept between two blocks of comments, so they are not merged.
+    pub max_consecutive: usize,
+    /// The number of blank lines before each section header, the first section of the file has none.
+    pub between_sections: usize,
+    /// The number of blank lines before a block of comments within a table,
+    /// `None` keeps the blank lines up to [BlankLines::max_consecutive].
+    pub before_comments: Option<usize>,
+    /// The number of blank lines between a block of comments and the key below it,
+    /// `None` keeps the blank lines up to [BlankLines::max_consecutive].
+    ///
+    /// Comments above a section header keep at least one blank line when they belong to the previous section.
+    pub after_comments: Option<usize>,
+}
+
+impl Default for BlankLines {
+    fn default() -> Self {
+        Self {
+            max_consecutive: 1,
+            between_sections: 1,
+            before_comments: None,
+            after_comments: None,
+        }
+    }
+}
+
+/// The line endings of a formatted file.
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    Display,
+    AsRefStr,
+    Eq,
+    PartialEq,
+    PartialOrd,
+    Hash,
+    EnumString,
+    Serialize,
+    Deserialize,
+)]
+pub enum LineEnding {
+    /// The line endings used by most lines of the original file.
+    Preserve,
+    /// `\n`
+    Lf,
+    /// `\r\n`
+    CrLf,
+    /// `\r\n` on Windows and `\n` on other platforms.
+    Native,
+}
+
+impl LineEnding {
+    /// Returns the line ending, `Preserve` uses the given line ending of the original file.
+    pub fn as_str<'a>(&self, original: &'a str) -> &'a str {
+        match self {
+            LineEnding::Preserve => original,
+            LineEnding::Lf => "\n",
+            LineEnding::CrLf => "\r\n",
+            LineEnding::Native if cfg!(windows) => "\r\n",
+            LineEnding::Native => "\n",
+        }
+    }
+}
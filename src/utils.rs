@@ -1,40 +1,193 @@
-use toml_edit::{Item, Key, Value};
-
-pub fn item_len(item: &Item) -> usize {
-    match item {
-        Item::None => 0,
-        Item::Value(val) => match val {
-            toml_edit::Value::String(str) => return str.value().char_indices().count(),
-            toml_edit::Value::Integer(int) => return int.to_repr().as_raw().char_indices().count(),
-            toml_edit::Value::Float(float) => {
-                return float.to_repr().as_raw().char_indices().count()
-            }
-            toml_edit::Value::Boolean(boolean) => {
-                return boolean.to_repr().as_raw().char_indices().count()
-            }
-            toml_edit::Value::Datetime(datetime) => {
-                return datetime.to_repr().as_raw().char_indices().count()
+use std::cmp::Ordering;
+
+use toml_edit::{Array, Decor, Value};
+
+use crate::comments::{ArrayComments, Comments};
+use crate::width::display_width;
+
+/// Sorts the values of an array with the given comparison function, optionally removing duplicate values.
+///
+/// The whitespace stays at its position in the array, so the layout of e.g. `["b", "a"]` is not shifted around.
+/// The comments of a value, see [ArrayComments], move along with the value they describe,
+/// an array with comments is written with each value on its own line.
+pub fn sort_array_by<F>(array: &mut Array, dedup: bool, mut compare: F)
+where
+    F: FnMut(&Value, &Value) -> Ordering,
+{
+    let comments = ArrayComments::from_array(array);
+
+    let mut values = array
+        .iter()
+        .cloned()
+        .zip(comments.values.iter().cloned())
+        .collect::<Vec<(Value, Comments)>>();
+    values.sort_by(|(value_1, _), (value_2, _)| compare(value_1, value_2));
+
+    if dedup {
+        values.dedup_by(|(removed, removed_comments), (kept, kept_comments)| {
+            let is_duplicate = raw_value(removed) == raw_value(kept);
+            if is_duplicate {
+                kept_comments.leading.append(&mut removed_comments.leading);
+                if kept_comments.trailing.is_none() {
+                    kept_comments.trailing = removed_comments.trailing.take();
+                }
             }
-            toml_edit::Value::Array(a) => a.iter().map(|i| item_len(&Item::Value(i.clone()))).sum(),
-            toml_edit::Value::InlineTable(inline_table) => table_len(inline_table.get_values()),
-        },
-        Item::Table(table) => table_len(table.get_values()),
-        Item::ArrayOfTables(array_table) => {
-            array_table.iter().map(|t| table_len(t.get_values())).sum()
+            is_duplicate
+        });
+    }
+
+    if comments.is_empty() {
+        let mut decors = array
+            .iter()
+            .map(|value| value.decor().clone())
+            .collect::<Vec<Decor>>();
+
+        // The last value keeps the decor of the original last value, e.g. a newline before the closing bracket.
+        if let Some(last_decor) = decors.pop() {
+            decors.truncate(values.len().saturating_sub(1));
+            decors.push(last_decor);
         }
+
+        array.clear();
+        for ((mut value, _), decor) in values.into_iter().zip(decors) {
+            *value.decor_mut() = decor;
+            array.push_formatted(value);
+        }
+        return;
+    }
+
+    let (indent, closing_indent) = array_indents(array);
+
+    let comments = ArrayComments {
+        values: values
+            .iter()
+            .map(|(_, comments)| comments.clone())
+            .collect(),
+        closing: comments.closing,
+    };
+
+    array.clear();
+    for (value, _) in values {
+        array.push_formatted(value);
     }
+
+    comments.wrap_array(array, &indent, &closing_indent, false);
 }
 
-pub fn table_len(key_values: Vec<(Vec<&Key>, &Value)>) -> usize {
-    key_values
-        .iter()
-        .map(|(keys, value)| -> usize {
-            let keys_len: usize = keys
-                .iter()
-                .map(|key| key.get().char_indices().count())
-                .sum();
-            let value_len = item_len(&Item::Value((*value).clone()));
-            keys_len + value_len
-        })
-        .sum()
+/// Returns the indentation of the values of a multi-line array relative to the closing bracket,
+/// and the indentation of the closing bracket.
+fn array_indents(array: &Array) -> (String, String) {
+    let mut raw = array.clone();
+    raw.decor_mut().clear();
+    let raw = raw.to_string();
+
+    let last_line = |text: &str| {
+        let line = text.rsplit('\n').next().unwrap_or_default();
+        match line.trim().is_empty() {
+            true => line.to_string(),
+            false => String::new(),
+        }
+    };
+
+    let closing_indent = last_line(raw.trim_end_matches(']'));
+    let value_indent = array
+        .get(0)
+        .and_then(|value| value.decor().prefix())
+        .map(last_line)
+        .unwrap_or_default();
+
+    let indent = match value_indent.strip_prefix(closing_indent.as_str()) {
+        Some(indent) if !indent.is_empty() => indent.to_string(),
+        _ => "    ".to_string(),
+    };
+
+    (indent, closing_indent)
+}
+
+/// Writes as many array values on each line as fit within the given width, the array may not contain comments.
+///
+/// The closing bracket is indented with `closing_indent`, the values one `indent` deeper.
+pub fn fill_array(
+    array: &mut Array,
+    indent: &str,
+    closing_indent: &str,
+    max_width: usize,
+    trailing_comma: bool,
+) {
+    array.set_trailing_comma(trailing_comma && !array.is_empty());
+
+    let closing = format!("\n{closing_indent}");
+    let indent = format!("{closing_indent}{indent}");
+    let indent_width = display_width(&indent);
+    let mut line_width = indent_width;
+    let last_idx = array.len().saturating_sub(1);
+    let has_trailing_comma = array.trailing_comma();
+    for (idx, value) in array.iter_mut().enumerate() {
+        // Each value is followed by a comma, except the last one without a trailing comma,
+        // and separated from the previous value on the line by a space.
+        let has_comma = idx < last_idx || has_trailing_comma;
+        let value_width = raw_value_len(value) + usize::from(has_comma);
+
+        if idx == 0 || line_width + 1 + value_width > max_width {
+            value.decor_mut().set_prefix(format!("\n{indent}"));
+            line_width = indent_width + value_width;
+        } else {
+            value.decor_mut().set_prefix(" ");
+            line_width += 1 + value_width;
+        }
+        value.decor_mut().set_suffix("");
+    }
+
+    match array.iter_mut().last() {
+        Some(last) if !has_trailing_comma => {
+            last.decor_mut().set_suffix(closing);
+            array.set_trailing("");
+        }
+        _ => array.set_trailing(&closing),
+    }
+}
+
+/// Returns the width of the value as it is written, without its decor.
+fn raw_value_len(value: &Value) -> usize {
+    let mut value = value.clone();
+    value.decor_mut().clear();
+    display_width(&value.to_string())
+}
+
+/// Returns the value without its decor, strings are returned in their parsed form so `'a'` equals `"a"`.
+fn raw_value(value: &Value) -> String {
+    match value.as_str() {
+        Some(str) => str.to_string(),
+        None => {
+            let mut value = value.clone();
+            value.decor_mut().clear();
+            value.to_string()
+        }
+    }
+}
+
+/// Escapes the value so it can be written between the quotes of a basic string.
+///
+/// Newlines are kept as they are when the value is written in a multi-line basic string.
+pub fn escape_basic_string(value: &str, multiline: bool) -> String {
+    let mut escaped = String::with_capacity(value.len());
+
+    for c in value.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '"' => escaped.push_str("\\\""),
+            '\n' if multiline => escaped.push('\n'),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\u{8}' => escaped.push_str("\\b"),
+            '\u{c}' => escaped.push_str("\\f"),
+            '\t' => escaped.push('\t'),
+            c if c <= '\u{1f}' || c == '\u{7f}' => {
+                escaped.push_str(&format!("\\u{:04X}", c as u32))
+            }
+            c => escaped.push(c),
+        }
+    }
+
+    escaped
 }
@@ -1 +1 @@
-mod fields;
+pub mod fields;
@@ -1,5 +1,3 @@
-use std::str::SplitTerminator;
-
 use regex::Regex;
 
 ///  The `homepage`, `documentation`, and `repository` field, if present, must consist of a single URL, including the scheme (e.g. https://example.org/, not just example.org.)
@@ -17,8 +15,8 @@ pub fn is_valid_license(license: &str) -> bool {
     let mut split_slash = license.split("/");
     //println!("{:?}", slit_or.collect::<Vec<&str>>());
 
-    split_or.all(|license| LICENSES.iter().any(|l| license == *l))
-        || split_slash.all(|license| LICENSES.iter().any(|l| license == *l))
+    split_or.all(|license| LICENSES.contains(&license))
+        || split_slash.all(|license| LICENSES.contains(&license))
 }
 
 /// The `name` field must use only alphanumeric characters or - or _, and cannot be empty.
@@ -28,41 +26,7 @@ pub fn is_valid_name() -> bool {
     false
 }
 
-mod tests {
-    use super::LICENSES;
-
-    #[test]
-    fn is_valid_license() {
-        let license_with_or = "MIT OR Apache-2.0";
-        let license_with_slash = "MIT/Apache-2.0";
-
-        for license in LICENSES {
-            assert!(super::is_valid_license(license));
-        }
-
-        assert!(super::is_valid_license(license_with_or));
-        assert!(super::is_valid_license(license_with_slash));
-    }
-
-    #[test]
-    fn is_invalid_license() {
-        let license_with_or = "MIT ORR Apache-2.0";
-        let license_with_slash = "MIT//Apache-2.0";
-        let invalid_license_1 = "TEST";
-        let invalid_license_2 = "MITT";
-        let invalid_license_3 = "Apache-";
-        let invalid_license_4 = "";
-
-        assert!(!super::is_valid_license(license_with_or));
-        assert!(!super::is_valid_license(license_with_slash));
-        assert!(!super::is_valid_license(invalid_license_1));
-        assert!(!super::is_valid_license(invalid_license_2));
-        assert!(!super::is_valid_license(invalid_license_3));
-        assert!(!super::is_valid_license(invalid_license_4));
-    }
-}
-
-const LICENSES: [&'static str; 496] = [
+const LICENSES: [&str; 496] = [
     "0BSD",
     "AAL",
     "Abstyles",
@@ -560,3 +524,38 @@ const LICENSES: [&'static str; 496] = [
     "StandardML-NJ",
     "wxWindows",
 ];
+
+#[cfg(test)]
+mod tests {
+    use super::LICENSES;
+
+    #[test]
+    fn is_valid_license() {
+        let license_with_or = "MIT OR Apache-2.0";
+        let license_with_slash = "MIT/Apache-2.0";
+
+        for license in LICENSES {
+            assert!(super::is_valid_license(license));
+        }
+
+        assert!(super::is_valid_license(license_with_or));
+        assert!(super::is_valid_license(license_with_slash));
+    }
+
+    #[test]
+    fn is_invalid_license() {
+        let license_with_or = "MIT ORR Apache-2.0";
+        let license_with_slash = "MIT//Apache-2.0";
+        let invalid_license_1 = "TEST";
+        let invalid_license_2 = "MITT";
+        let invalid_license_3 = "Apache-";
+        let invalid_license_4 = "";
+
+        assert!(!super::is_valid_license(license_with_or));
+        assert!(!super::is_valid_license(license_with_slash));
+        assert!(!super::is_valid_license(invalid_license_1));
+        assert!(!super::is_valid_license(invalid_license_2));
+        assert!(!super::is_valid_license(invalid_license_3));
+        assert!(!super::is_valid_license(invalid_license_4));
+    }
+}
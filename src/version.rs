@@ -0,0 +1,240 @@
+use std::fmt::{self, Display};
+
+use serde::{Deserialize, Serialize};
+
+/// The canonical form of dependency version requirements.
+///
+/// Only rewrites that don't change which versions match the requirement are performed.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, PartialOrd, Hash, Serialize, Deserialize)]
+pub struct VersionFormat {
+    /// Pads or trims caret requirements to the given number of components, e.g. `1` to `1.0.0` with 3 components.
+    /// Only zeros after the first non-zero component are added or removed, `0.1` and `0.1.0` are equal but `0` and `0.0.0` are not.
+    pub components: Option<usize>,
+    /// Also normalizes exact pins (`=1.2`) and ranges (`>= 1.2, < 2`).
+    /// Their versions are left as they are, only the whitespace around operators and commas is normalized.
+    pub normalize_comparators: bool,
+}
+
+/// A version requirement, e.g. `^1.2`, `=1.2.3` or `>=1.2, <2`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct VersionRequirement {
+    pub comparators: Vec<Comparator>,
+}
+
+/// A single comparator of a [VersionRequirement], e.g. `>=1.2`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Comparator {
+    pub op: Op,
+    /// The version components, e.g. `["1", "2", "*"]`.
+    pub components: Vec<String>,
+    /// The pre-release and build metadata, including the leading `-` or `+`.
+    pub suffix: String,
+}
+
+/// The operator of a [Comparator].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Op {
+    /// No operator, equal to [Op::Caret].
+    Default,
+    Caret,
+    Tilde,
+    Exact,
+    Greater,
+    GreaterEq,
+    Less,
+    LessEq,
+}
+
+impl Op {
+    const OPERATORS: [(&'static str, Op); 7] = [
+        (">=", Op::GreaterEq),
+        ("<=", Op::LessEq),
+        (">", Op::Greater),
+        ("<", Op::Less),
+        ("=", Op::Exact),
+        ("~", Op::Tilde),
+        ("^", Op::Caret),
+    ];
+
+    fn as_str(&self) -> &'static str {
+        Self::OPERATORS
+            .iter()
+            .find(|(_, op)| op == self)
+            .map(|(str, _)| *str)
+            .unwrap_or("")
+    }
+}
+
+impl VersionRequirement {
+    /// Parses the requirement, returns `None` when it is not a valid requirement.
+    pub fn parse(requirement: &str) -> Option<Self> {
+        let comparators = requirement
+            .split(',')
+            .map(Comparator::parse)
+            .collect::<Option<Vec<Comparator>>>()?;
+
+        Some(Self { comparators })
+    }
+
+    /// Returns whether the requirement contains a wildcard, e.g. `*` or `1.*`.
+    pub fn has_wildcard(&self) -> bool {
+        self.comparators
+            .iter()
+            .any(|comparator| comparator.components.iter().any(|c| is_wildcard(c)))
+    }
+
+    /// Returns whether this is a lone caret requirement, e.g. `1.2` or `^1.2`.
+    pub fn is_caret(&self) -> bool {
+        matches!(
+            self.comparators.as_slice(),
+            [Comparator {
+                op: Op::Default | Op::Caret,
+                ..
+            }]
+        )
+    }
+
+    /// Rewrites the requirement to the canonical form of the given format.
+    ///
+    /// Only a lone caret requirement is changed, e.g. `^1.2` to `1.2`, other requirements are only re-spaced when displayed.
+    pub fn normalize(&mut self, format: &VersionFormat) {
+        if !self.is_caret() {
+            return;
+        }
+
+        let comparator = &mut self.comparators[0];
+        comparator.op = Op::Default;
+
+        if let Some(components) = format.components {
+            comparator.set_components(components);
+        }
+    }
+}
+
+impl Display for VersionRequirement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (idx, comparator) in self.comparators.iter().enumerate() {
+            if idx > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{comparator}")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Comparator {
+    fn parse(comparator: &str) -> Option<Self> {
+        let comparator = comparator.trim();
+
+        let (op, version) = Op::OPERATORS
+            .iter()
+            .find_map(|(str, op)| comparator.strip_prefix(str).map(|version| (*op, version)))
+            .unwrap_or((Op::Default, comparator));
+        let version = version.trim_start();
+
+        let (version, suffix) = match version.find(['-', '+']) {
+            Some(idx) => version.split_at(idx),
+            None => (version, ""),
+        };
+
+        let components = version
+            .split('.')
+            .map(str::to_string)
+            .collect::<Vec<String>>();
+
+        let is_valid = components.len() <= 3
+            && components.iter().all(|component| {
+                is_wildcard(component)
+                    || (!component.is_empty() && component.chars().all(|c| c.is_ascii_digit()))
+            });
+
+        if !is_valid || (!suffix.is_empty() && components.len() != 3) {
+            return None;
+        }
+
+        Some(Self {
+            op,
+            components,
+            suffix: suffix.to_string(),
+        })
+    }
+
+    /// Pads or trims the components without changing the versions that match the caret comparator.
+    fn set_components(&mut self, count: usize) {
+        if !self.suffix.is_empty() || self.components.iter().any(|c| is_wildcard(c)) {
+            return;
+        }
+
+        // Zeros are only insignificant after the first non-zero component.
+        let first_non_zero = match self.components.iter().position(|c| c.parse() != Ok(0)) {
+            Some(idx) => idx,
+            None => return,
+        };
+
+        while self.components.len() < count.min(3) {
+            self.components.push("0".to_string());
+        }
+
+        while self.components.len() > count.max(first_non_zero + 1)
+            && self.components.last().map(String::as_str) == Some("0")
+        {
+            self.components.pop();
+        }
+    }
+}
+
+impl Display for Comparator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}{}{}",
+            self.op.as_str(),
+            self.components.join("."),
+            self.suffix
+        )
+    }
+}
+
+fn is_wildcard(component: &str) -> bool {
+    matches!(component, "*" | "x" | "X")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{VersionFormat, VersionRequirement};
+
+    fn normalize(requirement: &str, components: Option<usize>) -> String {
+        let mut requirement = VersionRequirement::parse(requirement).unwrap();
+        requirement.normalize(&VersionFormat {
+            components,
+            normalize_comparators: false,
+        });
+        requirement.to_string()
+    }
+
+    #[test]
+    fn drop_caret() {
+        assert_eq!(normalize("^1.0", None), "1.0");
+        assert_eq!(normalize(" 1.2.3 ", None), "1.2.3");
+    }
+
+    #[test]
+    fn pad_and_trim_components() {
+        assert_eq!(normalize("1", Some(3)), "1.0.0");
+        assert_eq!(normalize("1.0.0", Some(2)), "1.0");
+        assert_eq!(normalize("1.2.3", Some(1)), "1.2.3");
+        assert_eq!(normalize("0.1.0", Some(1)), "0.1");
+        assert_eq!(normalize("0.0", Some(3)), "0.0");
+        assert_eq!(normalize("1.0.0-alpha", Some(1)), "1.0.0-alpha");
+    }
+
+    #[test]
+    fn comparators() {
+        assert_eq!(normalize(">= 1.2,<2", None), ">=1.2, <2");
+        assert_eq!(normalize("= 1.2", Some(3)), "=1.2");
+        assert!(VersionRequirement::parse("1.*").unwrap().has_wildcard());
+        assert!(VersionRequirement::parse("1.2.3.4").is_none());
+    }
+}
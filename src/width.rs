@@ -0,0 +1,101 @@
+use toml_edit::{Decor, Key, KeyMut, Value};
+use unicode_width::UnicodeWidthChar;
+
+use crate::toml_config::Indent;
+
+/// The decor toml_edit writes around a key in a table when the key has no decor of its own.
+pub const KEY_DECOR: (&str, &str) = ("", " ");
+/// The decor toml_edit writes around a key in an inline table when the key has no decor of its own.
+pub const INLINE_KEY_DECOR: (&str, &str) = (" ", " ");
+/// The decor toml_edit writes around a value when the value has no decor of its own.
+pub const VALUE_DECOR: (&str, &str) = (" ", "");
+/// The decor toml_edit writes around the last value of an inline table when the value has no decor of its own.
+pub const TRAILING_VALUE_DECOR: (&str, &str) = (" ", " ");
+
+/// Returns the number of columns the widest line of the text takes up, measured in Unicode display width.
+///
+/// Wide characters, e.g. CJK ideographs and emoji, take up two columns,
+/// combining marks and other zero-width characters none and a tab [Indent::TAB_WIDTH] columns.
+pub fn display_width(text: &str) -> usize {
+    text.lines().map(line_width).max().unwrap_or(0)
+}
+
+/// Returns the number of columns of the widest line of the text when its first line starts at column `offset`.
+pub fn width_at(offset: usize, text: &str) -> usize {
+    text.lines()
+        .enumerate()
+        .map(|(idx, line)| if idx == 0 { offset } else { 0 } + line_width(line))
+        .max()
+        .unwrap_or(offset)
+}
+
+/// Returns the column after the text when its first line starts at column `offset`.
+pub fn column_after(offset: usize, text: &str) -> usize {
+    match text.rsplit_once('\n') {
+        Some((_, last_line)) => line_width(last_line),
+        None => offset + line_width(text),
+    }
+}
+
+/// Returns the text of the key as it is written, including its decor or else the given default decor.
+pub fn key_text(key: &Key, default: (&str, &str)) -> String {
+    decorated(key.to_repr().as_raw(), key.decor(), default)
+}
+
+/// Returns the text of the key as it is written, see [key_text].
+pub fn key_mut_text(key: &KeyMut, default: (&str, &str)) -> String {
+    decorated(key.to_repr().as_raw(), key.decor(), default)
+}
+
+/// Returns the text of the value as it is written, including its decor or else the given default decor.
+pub fn value_text(value: &Value, default: (&str, &str)) -> String {
+    let mut raw = value.clone();
+    raw.decor_mut().clear();
+
+    decorated(&raw.to_string(), value.decor(), default)
+}
+
+/// Returns the number of columns of the widest line of `key = value` as it is written in a table,
+/// `offset` being the width of the line before the key, e.g. the parents of a dotted key.
+pub fn key_value_width(offset: usize, key: &str, value: &Value) -> usize {
+    width_at(offset, &format!("{key}={}", value_text(value, VALUE_DECOR)))
+}
+
+fn decorated(raw: &str, decor: &Decor, default: (&str, &str)) -> String {
+    format!(
+        "{}{raw}{}",
+        decor.prefix().unwrap_or(default.0),
+        decor.suffix().unwrap_or(default.1)
+    )
+}
+
+fn line_width(line: &str) -> usize {
+    line.chars().map(char_width).sum()
+}
+
+/// Returns the number of columns of a character.
+pub fn char_width(c: char) -> usize {
+    match c {
+        '\t' => Indent::TAB_WIDTH,
+        c => c.width().unwrap_or(0),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{display_width, Indent};
+
+    #[test]
+    fn unicode_width() {
+        assert_eq!(display_width("abc"), 3);
+        assert_eq!(display_width("日本語"), 6);
+        assert_eq!(display_width("e\u{301}"), 1);
+        assert_eq!(display_width("🦀 crab"), 7);
+        assert_eq!(display_width("ab\nabcd\nabc"), 4);
+        assert_eq!(display_width("한국어"), 6);
+        assert_eq!(display_width("ＡＢ"), 4);
+        assert_eq!(display_width("a\u{200B}b"), 2);
+        assert_eq!(display_width("\ta"), Indent::TAB_WIDTH + 1);
+        assert_eq!(display_width("a\u{7}"), 1);
+    }
+}
@@ -1,4 +1,11 @@
-use cargo_toml_fmt::{cargo_toml::CargoToml, toml_config::TomlFormatConfig};
+use cargo_toml_fmt::{
+    cargo_toml::CargoToml,
+    toml_config::{
+        ArrayPacking, BlankLines, DependencyStyle, DottedKeyStyle, Indent, LineEnding, QuoteStyle,
+        TomlFormatConfig,
+    },
+    version::VersionFormat,
+};
 
 #[test]
 fn append_new_line_after_section() {
@@ -183,6 +190,37 @@ features = ["a","b"]
     assert_eq!(toml.toml_document.to_string(), AFTER);
 }
 
+#[test]
+fn wrap_table_keeps_comments() {
+    const BEFORE: &str = r#"[dependencies]
+a={version="0.4.1"}
+# Serialization support.
+b={version="0.4.1",path="some_path"} # Serialization.
+c={version="0.4.1",path="some_path"} # Parsing.
+"#;
+    const AFTER: &str = r#"[dependencies]
+a={version="0.4.1"}
+
+# Serialization support.
+[dependencies.b] # Serialization.
+version = "0.4.1"
+path = "some_path"
+
+[dependencies.c] # Parsing.
+version = "0.4.1"
+path = "some_path"
+"#;
+
+    let mut config = TomlFormatConfig::new();
+    config.wrap_table = Some(20);
+
+    let mut toml = CargoToml::from_config(BEFORE.to_string(), config).unwrap();
+
+    toml.format().unwrap();
+
+    assert_eq!(toml.toml_document.to_string(), AFTER);
+}
+
 #[test]
 fn comments() {
     const BEFORE: &str = r#"[dependencies]
@@ -212,3 +250,980 @@ features = ["a","b"]
 
     assert_eq!(toml.toml_document.to_string(), AFTER);
 }
+
+#[test]
+fn wrap_description() {
+    const BEFORE: &str = r#"[package]
+description = "A \"very\" long description  that does not fit within the configured width."
+"#;
+    const AFTER: &str = r#"[package]
+description = """
+A \"very\" long description  \
+that does not fit within the \
+configured width."""
+"#;
+
+    let mut config = TomlFormatConfig::new();
+    config.wrap_description = Some(30);
+
+    let mut toml = CargoToml::from_config(BEFORE.to_string(), config).unwrap();
+
+    toml.format().unwrap();
+
+    assert_eq!(toml.toml_document.to_string(), AFTER);
+
+    let before = BEFORE.parse::<toml_edit::Document>().unwrap();
+    let after = AFTER.parse::<toml_edit::Document>().unwrap();
+    assert_eq!(
+        before["package"]["description"].as_str(),
+        after["package"]["description"].as_str()
+    );
+}
+
+#[test]
+fn wrap_description_counts_closing_quotes() {
+    const BEFORE: &str = r#"[package]
+description = "aaaa bbbb cccc ddd eeee ffff gggg hhh"
+"#;
+    const AFTER: &str = r#"[package]
+description = """
+aaaa bbbb cccc ddd \
+eeee ffff gggg \
+hhh"""
+"#;
+
+    let mut config = TomlFormatConfig::new();
+    config.wrap_description = Some(20);
+
+    let mut toml = CargoToml::from_config(BEFORE.to_string(), config).unwrap();
+
+    toml.format().unwrap();
+
+    assert_eq!(toml.toml_document.to_string(), AFTER);
+    assert!(AFTER
+        .lines()
+        .all(|line| line.len() <= 20 || line.starts_with("description")));
+}
+
+#[test]
+fn wrap_description_without_spaces() {
+    const BEFORE: &str = r#"[package]
+description = "https://example.com/a-very-long-link-to-the-documentation-of-the-crate"
+"#;
+
+    let mut config = TomlFormatConfig::new();
+    config.wrap_description = Some(30);
+
+    let mut toml = CargoToml::from_config(BEFORE.to_string(), config).unwrap();
+
+    toml.format().unwrap();
+
+    assert_eq!(toml.toml_document.to_string(), BEFORE);
+    assert!(toml.warnings.is_empty());
+}
+
+#[test]
+fn use_multiline_strings() {
+    const BEFORE: &str = r#"[package.metadata]
+a = "first \"line\"\nsecond 'line'"
+b = { c = "first line\nsecond line" }
+d = "single line"
+"#;
+    const AFTER: &str = r#"[package.metadata]
+a = """
+first \"line\"
+second 'line'"""
+b = { c = '''
+first line
+second line''' }
+d = "single line"
+"#;
+
+    let mut config = TomlFormatConfig::new();
+    config.use_multiline_strings = true;
+    config.prefer_literal_multiline_strings = true;
+
+    let mut toml = CargoToml::from_config(BEFORE.to_string(), config).unwrap();
+
+    toml.format().unwrap();
+
+    assert_eq!(toml.toml_document.to_string(), AFTER);
+}
+
+#[test]
+fn normalize_string_quotes() {
+    const BEFORE: &str = r#"[dependencies]
+a = { version = '0.1', path = 'C:\path' }
+b = "C:\\path"
+c = 'it"s'
+d = "it's"
+"#;
+    const AFTER: &str = r#"[dependencies]
+a = { version = "0.1", path = 'C:\path' }
+b = 'C:\path'
+c = 'it"s'
+d = "it's"
+"#;
+
+    let mut config = TomlFormatConfig::new();
+    config.string_quote_style = Some(QuoteStyle::Double);
+
+    let mut toml = CargoToml::from_config(BEFORE.to_string(), config).unwrap();
+
+    toml.format().unwrap();
+
+    assert_eq!(toml.toml_document.to_string(), AFTER);
+}
+
+#[test]
+fn collapse_table() {
+    const BEFORE: &str = r#"[dependencies]
+a = "0.1"
+
+[dependencies.b]
+version = "0.4.1"
+features = ["a"]
+
+[dependencies.c]
+version = "0.4.1"
+path = "some_very_long_path"
+
+# Comments are never lost.
+[dependencies.d]
+version = "0.4.1"
+
+[target.'cfg(unix)'.dependencies.e]
+version = "0.4.1"
+"#;
+    const AFTER: &str = r#"[dependencies]
+a = "0.1"
+b = { version = "0.4.1", features = ["a"] }
+
+[dependencies.c]
+version = "0.4.1"
+path = "some_very_long_path"
+
+# Comments are never lost.
+[dependencies.d]
+version = "0.4.1"
+
+[target.'cfg(unix)'.dependencies]
+e = { version = "0.4.1" }
+"#;
+
+    let mut config = TomlFormatConfig::new();
+    config.collapse_tables = true;
+    config.wrap_table = Some(45);
+
+    let mut toml = CargoToml::from_config(BEFORE.to_string(), config).unwrap();
+
+    toml.format().unwrap();
+
+    assert_eq!(toml.toml_document.to_string(), AFTER);
+}
+
+#[test]
+fn dependency_short_style() {
+    const BEFORE: &str = r#"[dependencies]
+a = { version = "0.1" } # The a crate.
+b = { version = "0.1", optional = true }
+c = { workspace = true } # The c crate.
+
+[target.'cfg(unix)'.dev-dependencies]
+d = { version = "0.1" }
+"#;
+    const AFTER: &str = r#"[dependencies]
+a = "0.1" # The a crate.
+b = { version = "0.1", optional = true }
+c.workspace = true # The c crate.
+
+[target.'cfg(unix)'.dev-dependencies]
+d = "0.1"
+"#;
+
+    let mut config = TomlFormatConfig::new();
+    config.dependency_style = Some(DependencyStyle::Short);
+    config.dotted_workspace_dependencies = true;
+
+    let mut toml = CargoToml::from_config(BEFORE.to_string(), config).unwrap();
+    toml.format().unwrap();
+
+    assert_eq!(toml.toml_document.to_string(), AFTER);
+}
+
+#[test]
+fn dependency_table_style() {
+    const BEFORE: &str = r#"[dependencies]
+a = "0.1" # The a crate.
+b = { workspace = true }
+
+[workspace.dependencies]
+c = "0.1"
+
+[package.metadata.tool.dependencies]
+d = "0.1"
+"#;
+    const AFTER: &str = r#"[dependencies]
+a = { version = "0.1" } # The a crate.
+b = { workspace = true }
+
+[workspace.dependencies]
+c = { version = "0.1" }
+
+[package.metadata.tool.dependencies]
+d = "0.1"
+"#;
+
+    let mut config = TomlFormatConfig::new();
+    config.dependency_style = Some(DependencyStyle::Table);
+
+    let mut toml = CargoToml::from_config(BEFORE.to_string(), config).unwrap();
+    toml.format().unwrap();
+
+    assert_eq!(toml.toml_document.to_string(), AFTER);
+}
+
+#[test]
+fn normalize_version_requirements() {
+    const BEFORE: &str = r#"[dependencies]
+a = "^1" # The a crate.
+b = { version = "1.0.0", optional = true }
+c = "=1.2"
+d = ">= 1.2,<2"
+e = "0.0"
+f = "*"
+
+[dependencies.g]
+version = "^0.1.0"
+"#;
+    const AFTER: &str = r#"[dependencies]
+a = "1.0" # The a crate.
+b = { version = "1.0", optional = true }
+c = "=1.2"
+d = ">= 1.2,<2"
+e = "0.0"
+f = "*"
+
+[dependencies.g]
+version = "0.1"
+"#;
+
+    let mut config = TomlFormatConfig::new();
+    config.version_format = Some(VersionFormat {
+        components: Some(2),
+        normalize_comparators: false,
+    });
+    config.lint_wildcard_versions = true;
+
+    let mut toml = CargoToml::from_config(BEFORE.to_string(), config).unwrap();
+    toml.format().unwrap();
+
+    assert_eq!(toml.toml_document.to_string(), AFTER);
+    assert_eq!(
+        toml.warnings,
+        vec!["Dependency `f` has a wildcard version requirement `*`.".to_string()]
+    );
+}
+
+#[test]
+fn normalize_version_comparators() {
+    const BEFORE: &str = r#"[dependencies]
+a = "= 1.2"
+b = ">= 1.2,<2"
+"#;
+    const AFTER: &str = r#"[dependencies]
+a = "=1.2"
+b = ">=1.2, <2"
+"#;
+
+    let mut config = TomlFormatConfig::new();
+    config.version_format = Some(VersionFormat {
+        components: Some(3),
+        normalize_comparators: true,
+    });
+
+    let mut toml = CargoToml::from_config(BEFORE.to_string(), config).unwrap();
+    toml.format().unwrap();
+
+    assert_eq!(toml.toml_document.to_string(), AFTER);
+}
+
+#[test]
+fn expand_dotted_keys() {
+    const BEFORE: &str = r#"[package]
+name = "a"
+
+# Docs.
+metadata.docs.rs.all-features = true
+
+[dependencies]
+a.workspace = true # The a crate.
+b = { version = "0.1", x.y = 2 }
+"#;
+    const AFTER: &str = r#"[package]
+name = "a"
+
+# Docs.
+[package.metadata.docs.rs]
+all-features = true
+
+[dependencies]
+a = { workspace = true } # The a crate.
+b = { version = "0.1", x = { y = 2 } }
+"#;
+
+    let mut config = TomlFormatConfig::new();
+    config.dotted_keys = Some(DottedKeyStyle::Expand);
+
+    let mut toml = CargoToml::from_config(BEFORE.to_string(), config).unwrap();
+    toml.format().unwrap();
+
+    assert_eq!(toml.toml_document.to_string(), AFTER);
+}
+
+#[test]
+fn collapse_dotted_keys() {
+    const BEFORE: &str = r#"[package]
+name = "a"
+
+# Docs.
+[package.metadata.docs.rs]
+all-features = true
+
+[dependencies.a]
+workspace = true # The a crate.
+
+[dependencies.b]
+version = "0.1"
+features = ["a"]
+
+[profile.dev.package.c]
+opt-level = 3
+"#;
+    const AFTER: &str = r#"[package]
+name = "a"
+
+# Docs.
+metadata.docs.rs.all-features = true
+
+[dependencies]
+a.workspace = true # The a crate.
+
+[dependencies.b]
+version = "0.1"
+features = ["a"]
+
+[profile.dev.package.c]
+opt-level = 3
+"#;
+
+    let mut config = TomlFormatConfig::new();
+    config.dotted_keys = Some(DottedKeyStyle::Collapse);
+
+    let mut toml = CargoToml::from_config(BEFORE.to_string(), config).unwrap();
+    toml.format().unwrap();
+
+    assert_eq!(toml.toml_document.to_string(), AFTER);
+}
+
+#[test]
+fn preserve_array_comments() {
+    const BEFORE: &str = r#"[workspace]
+a=[ # The first crates.
+  "a", # The a crate.
+  # The b crate.
+  "b"
+  # More crates later.
+]
+b=[
+  "a",   "b", # The b crate.
+]
+c=["a",   "b"]
+"#;
+    const AFTER: &str = r#"[workspace]
+a = [
+    # The first crates.
+    "a", # The a crate.
+    # The b crate.
+    "b"
+    # More crates later.
+]
+b = [
+    "a",
+    "b", # The b crate.
+]
+c = ["a", "b"]
+"#;
+
+    let mut config = TomlFormatConfig::new();
+    config.table_formatting = true;
+    config.wrap_array = Some(50);
+
+    let mut toml = CargoToml::from_config(BEFORE.to_string(), config).unwrap();
+    toml.format().unwrap();
+
+    assert_eq!(toml.toml_document.to_string(), AFTER);
+}
+
+#[test]
+fn table_formatting_keeps_comments() {
+    const BEFORE: &str = r#"# The file header.
+
+
+# The package.
+[package]   # The package section.
+name="a"    # See https://example.com/#name.
+"#;
+    const AFTER: &str = r#"# The file header.
+
+# The package.
+[package] # The package section.
+name = "a" # See https://example.com/#name.
+"#;
+
+    let mut config = TomlFormatConfig::new();
+    config.table_formatting = true;
+
+    let mut toml = CargoToml::from_config(BEFORE.to_string(), config).unwrap();
+    toml.format().unwrap();
+
+    assert_eq!(toml.toml_document.to_string(), AFTER);
+}
+
+#[test]
+fn directives_leave_items_untouched() {
+    const BEFORE: &str = r#"[package]
+name="a"
+# toml-fmt: skip
+edition  =  "2021"
+
+# toml-fmt: sort-off
+[dependencies]
+b="0.1"
+a="0.1"
+
+[features]
+# cargo-toml-fmt: off
+z=[ "b",  "a" ]
+y=[]
+# toml-fmt: on
+x=[ "c" ]
+"#;
+
+    const AFTER: &str = r#"[package]
+name = "a"
+# toml-fmt: skip
+edition  =  "2021"
+# toml-fmt: sort-off
+[dependencies]
+b = "0.1"
+a = "0.1"
+[features]
+# cargo-toml-fmt: off
+z=[ "b",  "a" ]
+y=[]
+# toml-fmt: on
+x = ["c"]
+"#;
+
+    let mut config = TomlFormatConfig::new();
+    config.table_formatting = true;
+    config.order_table_keys_alphabetically = true;
+
+    let mut toml = CargoToml::from_config(BEFORE.to_string(), config).unwrap();
+
+    toml.format().unwrap();
+
+    assert_eq!(toml.toml_document.to_string(), AFTER);
+}
+
+#[test]
+fn directives_off_region_ends_before_next_section() {
+    const BEFORE: &str = r#"[package]
+name="a"
+
+# toml-fmt: off
+[features]
+z=[ "b",  "a" ]
+y=[]
+# toml-fmt: on
+[dev-dependencies]
+y="1"
+x="1"
+"#;
+
+    const AFTER: &str = r#"[package]
+name = "a"
+
+# toml-fmt: off
+[features]
+z=[ "b",  "a" ]
+y=[]
+# toml-fmt: on
+[dev-dependencies]
+x = "1"
+y = "1"
+"#;
+
+    let mut config = TomlFormatConfig::new();
+    config.table_formatting = true;
+    config.order_table_keys_alphabetically = true;
+
+    let mut toml = CargoToml::from_config(BEFORE.to_string(), config).unwrap();
+
+    toml.format().unwrap();
+
+    assert_eq!(toml.toml_document.to_string(), AFTER);
+}
+
+#[test]
+fn directives_keep_skipped_sections_in_place() {
+    const BEFORE: &str = r#"[package]
+name = "a"
+
+[features]
+default = []
+
+# toml-fmt: skip
+[dependencies]
+b = "0.1"
+
+[dependencies.a]
+version = "0.1"
+
+[dev-dependencies]
+c = "0.1"
+"#;
+
+    const AFTER: &str = r#"[package]
+name = "a"
+
+[dev-dependencies]
+c = "0.1"
+
+# toml-fmt: skip
+[dependencies]
+b = "0.1"
+
+[dependencies.a]
+version = "0.1"
+
+[features]
+default = []
+"#;
+
+    let mut config = TomlFormatConfig::new();
+    config.order_sections = true;
+    config.collapse_tables = true;
+    config.dotted_keys = Some(DottedKeyStyle::Collapse);
+    config.wrap_table = Some(80);
+
+    let mut toml = CargoToml::from_config(BEFORE.to_string(), config).unwrap();
+
+    toml.format().unwrap();
+
+    assert_eq!(toml.toml_document.to_string(), AFTER);
+}
+
+#[test]
+fn wrap_array_layout() {
+    const BEFORE: &str = r#"[a]
+a=["a","b","c","d","e","f","g"]
+"#;
+
+    const ONE_PER_LINE: &str =
+        "[a]\na=[\n\t\"a\",\n\t\"b\",\n\t\"c\",\n\t\"d\",\n\t\"e\",\n\t\"f\",\n\t\"g\",\n]\n";
+
+    const FILL: &str = r#"[a]
+a=[
+  "a", "b", "c",
+  "d", "e", "f",
+  "g"
+]
+"#;
+
+    let mut config = TomlFormatConfig::new();
+    config.wrap_array = Some(16);
+    config.wrap_indent = Indent::Tab;
+    config.wrap_trailing_comma = true;
+
+    let mut toml = CargoToml::from_config(BEFORE.to_string(), config.clone()).unwrap();
+    toml.format().unwrap();
+    assert_eq!(toml.toml_document.to_string(), ONE_PER_LINE);
+
+    config.wrap_indent = Indent::Spaces(2);
+    config.wrap_trailing_comma = false;
+    config.wrap_packing = ArrayPacking::Fill;
+
+    let mut toml = CargoToml::from_config(BEFORE.to_string(), config).unwrap();
+    toml.format().unwrap();
+    assert_eq!(toml.toml_document.to_string(), FILL);
+}
+
+#[test]
+fn fill_array_without_trailing_comma() {
+    const BEFORE: &str = r#"[a]
+a = ["a", "b", "c"]
+"#;
+
+    // The last value fits exactly, as no comma is written after it.
+    const AFTER: &str = r#"[a]
+a = [
+  "a", "b", "c"
+]
+"#;
+
+    let mut config = TomlFormatConfig::new();
+    config.wrap_array = Some(15);
+    config.wrap_indent = Indent::Spaces(2);
+    config.wrap_packing = ArrayPacking::Fill;
+
+    let mut toml = CargoToml::from_config(BEFORE.to_string(), config).unwrap();
+    toml.format().unwrap();
+    assert_eq!(toml.toml_document.to_string(), AFTER);
+}
+
+#[test]
+fn wrap_nested_arrays() {
+    const BEFORE: &str = r#"[package]
+include=["src/**/*.rs","Cargo.toml"]
+
+[dependencies]
+a={version="0.1",features=["first","second"]}
+
+[target.'cfg(unix)'.dependencies]
+b={version="0.1",features=["first","second"]}
+
+[[bin]]
+name="a"
+required-features=["first","second"]
+"#;
+
+    const AFTER: &str = r#"[package]
+include=["src/**/*.rs","Cargo.toml"]
+
+[dependencies]
+a={version="0.1",features=[
+    "first",
+    "second",
+]}
+
+[target.'cfg(unix)'.dependencies]
+b={version="0.1",features=[
+    "first",
+    "second",
+]}
+
+[[bin]]
+name="a"
+required-features=[
+    "first",
+    "second",
+]
+"#;
+
+    let mut config = TomlFormatConfig::new();
+    config.wrap_array = Some(30);
+    config.wrap_trailing_comma = true;
+    config.exclude_tables_from_wrapping = vec!["package".to_string()];
+
+    let mut toml = CargoToml::from_config(BEFORE.to_string(), config).unwrap();
+    toml.format().unwrap();
+
+    assert_eq!(toml.toml_document.to_string(), AFTER);
+}
+
+#[test]
+fn wrap_by_display_width() {
+    const BEFORE: &str = r#"[a]
+a = ["日本語", "中文"]
+b = ["abc", "de"]
+"#;
+
+    const AFTER: &str = r#"[a]
+a = [
+    "日本語",
+    "中文"
+]
+b = ["abc", "de"]
+"#;
+
+    let mut config = TomlFormatConfig::new();
+    config.wrap_array = Some(18);
+
+    let mut toml = CargoToml::from_config(BEFORE.to_string(), config).unwrap();
+    toml.format().unwrap();
+
+    assert_eq!(toml.toml_document.to_string(), AFTER);
+}
+
+#[test]
+fn wrap_nested_tables() {
+    const BEFORE: &str = r#"[target.'cfg(unix)'.dependencies]
+a={version="0.4.1",path="some_path"}
+b="0.1"
+
+[[bin]]
+name="a"
+metadata={description="some long description"}
+
+[[bin]]
+name="b"
+"#;
+    const AFTER: &str = r#"[target.'cfg(unix)'.dependencies]
+b="0.1"
+
+[target.'cfg(unix)'.dependencies.a]
+version = "0.4.1"
+path = "some_path"
+
+[[bin]]
+name="a"
+
+[bin.metadata]
+description = "some long description"
+
+[[bin]]
+name="b"
+"#;
+
+    let mut config = TomlFormatConfig::new();
+    config.wrap_table = Some(30);
+
+    let mut toml = CargoToml::from_config(BEFORE.to_string(), config).unwrap();
+    toml.format().unwrap();
+
+    assert_eq!(toml.toml_document.to_string(), AFTER);
+}
+
+#[test]
+fn crlf_round_trip() {
+    const BEFORE: &str = "[package]\r\nname=\"a\"\r\n[dependencies]\r\nb=\"0.1\" # The b crate.\r\na=\"0.1\"\r\n\r\n\r\n";
+    const AFTER: &str = "[package]\r\nname = \"a\"\r\n\r\n[dependencies]\r\na = \"0.1\"\r\nb = \"0.1\" # The b crate.\r\n";
+
+    let mut toml = CargoToml::default(BEFORE.to_string()).unwrap();
+    toml.format().unwrap();
+
+    assert_eq!(toml.line_ending(), "\r\n");
+    assert_eq!(toml.to_string(), AFTER);
+
+    // Formatting the formatted file doesn't change it.
+    let mut toml = CargoToml::default(AFTER.to_string()).unwrap();
+    toml.format().unwrap();
+
+    assert_eq!(toml.to_string(), AFTER);
+}
+
+#[test]
+fn line_ending_and_bom() {
+    const BEFORE: &str = "\u{feff}[package]\r\nname = \"a\"\r\n";
+
+    let mut config = TomlFormatConfig::new();
+    let toml = CargoToml::from_config(BEFORE.to_string(), config.clone()).unwrap();
+    assert_eq!(toml.to_string(), BEFORE);
+
+    config.line_ending = LineEnding::Lf;
+    config.strip_bom = true;
+    config.final_newline = true;
+    let toml = CargoToml::from_config(format!("{BEFORE}\r\n  "), config).unwrap();
+    assert_eq!(toml.to_string(), "[package]\nname = \"a\"\n");
+}
+
+#[test]
+fn blank_line_policy() {
+    const BEFORE: &str = r#"[package]
+
+name="a"
+
+
+version="0.1.0"
+# The edition.
+edition="2021"
+[dependencies]
+a="0.1"
+
+
+
+# Dangling comment.
+
+
+# The b crate.
+
+b="0.1"
+"#;
+    const AFTER: &str = r#"[package]
+name = "a"
+
+version = "0.1.0"
+
+# The edition.
+edition = "2021"
+
+[dependencies]
+a = "0.1"
+
+# Dangling comment.
+
+# The b crate.
+b = "0.1"
+"#;
+
+    let mut config = TomlFormatConfig::new();
+    config.table_formatting = true;
+    config.blank_lines = Some(BlankLines {
+        before_comments: Some(1),
+        after_comments: Some(0),
+        ..BlankLines::default()
+    });
+
+    let mut toml = CargoToml::from_config(BEFORE.to_string(), config).unwrap();
+    toml.format().unwrap();
+
+    assert_eq!(toml.toml_document.to_string(), AFTER);
+}
+
+#[test]
+fn blank_lines_without_consecutive_blank_lines() {
+    const BEFORE: &str = r#"[package]
+name="a"
+
+
+a.b=1
+a.c=2
+# Dangling comment.
+
+# The edition.
+edition="2021"
+"#;
+    const AFTER: &str = r#"[package]
+name = "a"
+a.b = 1
+a.c = 2
+# Dangling comment.
+
+# The edition.
+edition = "2021"
+"#;
+
+    let mut config = TomlFormatConfig::new();
+    config.table_formatting = true;
+    config.blank_lines = Some(BlankLines {
+        max_consecutive: 0,
+        ..BlankLines::default()
+    });
+
+    let mut toml = CargoToml::from_config(BEFORE.to_string(), config).unwrap();
+
+    // A blank line before a key within a dotted table would be written within the dotted key.
+    toml.toml_document["package"]["a"]
+        .as_table_mut()
+        .unwrap()
+        .key_decor_mut("c")
+        .unwrap()
+        .set_prefix("\n\n");
+
+    toml.format().unwrap();
+
+    assert_eq!(toml.toml_document.to_string(), AFTER);
+}
+
+#[test]
+fn align_keys_and_comments() {
+    const BEFORE: &str = r#"[dependencies]
+a="0.1" # The a crate.
+serde={version="1.0"} # Serialization.
+
+tokio="1"
+regex={version="1.0",features=["std"]}
+some-very-long-crate-name="0.1"
+"#;
+    const AFTER: &str = r#"[dependencies]
+a     = "0.1"               # The a crate.
+serde = { version = "1.0" } # Serialization.
+
+tokio                     = "1"
+regex = { version = "1.0", features = ["std"] }
+some-very-long-crate-name = "0.1"
+"#;
+
+    let mut config = TomlFormatConfig::new();
+    config.table_formatting = true;
+    config.wrap_array = Some(50);
+    config.blank_lines = Some(BlankLines::default());
+    config.align_keys = true;
+    config.align_comments = true;
+
+    let mut toml = CargoToml::from_config(BEFORE.to_string(), config).unwrap();
+    toml.format().unwrap();
+
+    assert_eq!(toml.toml_document.to_string(), AFTER);
+}
+
+#[test]
+fn indented_lines_stay_within_width() {
+    const BEFORE: &str = r#"[target.'cfg(unix)'.dependencies]
+a = { version = "0.1" }
+b = "0.1"
+abc = "0.1"
+"#;
+    const AFTER: &str = r#"[target.'cfg(unix)'.dependencies]
+    b = "0.1"
+    abc = "0.1"
+
+[target.'cfg(unix)'.dependencies.a]
+    version = "0.1"
+"#;
+
+    // Both lines fit without the indentation.
+    let mut config = TomlFormatConfig::new();
+    config.table_indent = Some(Indent::Spaces(4));
+    config.wrap_table = Some(23);
+    config.wrap_array = Some(14);
+    config.align_keys = true;
+
+    let mut toml = CargoToml::from_config(BEFORE.to_string(), config).unwrap();
+    toml.format().unwrap();
+
+    assert_eq!(toml.toml_document.to_string(), AFTER);
+}
+
+#[test]
+fn indent_sub_tables() {
+    const BEFORE: &str = r#"[dependencies]
+b="0.1"
+
+[dependencies.a]
+# The version.
+version="0.1"
+features=["first","second"]
+
+[[bin]]
+name="a"
+"#;
+    const AFTER: &str = "[dependencies]
+b = \"0.1\"
+
+[dependencies.a]
+\t# The version.
+\tversion = \"0.1\"
+\tfeatures = [
+\t    \"first\",
+\t    \"second\",
+\t]
+
+[[bin]]
+\tname = \"a\"
+";
+
+    let mut config = TomlFormatConfig::new();
+    config.table_formatting = true;
+    config.table_indent = Some(Indent::Tab);
+    config.blank_lines = Some(BlankLines::default());
+    config.wrap_array = Some(30);
+    config.wrap_trailing_comma = true;
+
+    let mut toml = CargoToml::from_config(BEFORE.to_string(), config).unwrap();
+    toml.format().unwrap();
+
+    assert_eq!(toml.toml_document.to_string(), AFTER);
+}
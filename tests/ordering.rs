@@ -1,6 +1,7 @@
 use cargo_toml_fmt::{
     cargo_toml::CargoToml,
-    package_order::{TomlSection, TomlSort},
+    comparator::Collation,
+    package_order::{FeatureSort, FeatureValueSort, TomlSection},
     toml_config::TomlFormatConfig,
 };
 use strum::VariantNames;
@@ -244,9 +245,9 @@ f={b=1,a=2}
     config.order_section_keys_by_group_alphabetically = true;
 
     let mut toml = CargoToml::from_config(BEFORE.to_string(), config).unwrap();
-    println!("{}", toml.toml_document.to_string());
+    println!("{}", toml.toml_document);
     toml.format().unwrap();
-    println!("{}", toml.toml_document.to_string());
+    println!("{}", toml.toml_document);
     assert_eq!(toml.toml_document.to_string(), AFTER);
 }
 
@@ -292,8 +293,325 @@ k={b=1,a=2}
     config.order_section_keys_by_group_alphabetically = true;
 
     let mut toml = CargoToml::from_config(BEFORE.to_string(), config).unwrap();
-    println!("{}", toml.toml_document.to_string());
+    println!("{}", toml.toml_document);
     toml.format().unwrap();
-    println!("{}", toml.toml_document.to_string());
+    println!("{}", toml.toml_document);
+    assert_eq!(toml.toml_document.to_string(), AFTER);
+}
+
+#[test]
+fn order_sub_tables() {
+    const BEFORE: &str = r#"[profile.custom]
+a = "a"
+[profile.bench]
+a = "a"
+[profile.release]
+a = "a"
+[profile.dev]
+a = "a"
+[target.'cfg(unix)'.build-dependencies]
+a = "a"
+[target.'cfg(unix)'.dependencies]
+a = "a"
+[target.'cfg(windows)'.dependencies]
+a = "a"
+[target.'cfg(unix)'.dev-dependencies]
+a = "a"
+[patch.my-registry]
+a = "a"
+[patch.crates-io]
+a = "a"
+[patch.a-registry]
+a = "a"
+"#;
+
+    const AFTER: &str = r#"[target.'cfg(unix)'.dependencies]
+a = "a"
+[target.'cfg(unix)'.dev-dependencies]
+a = "a"
+[target.'cfg(unix)'.build-dependencies]
+a = "a"
+[target.'cfg(windows)'.dependencies]
+a = "a"
+[patch.crates-io]
+a = "a"
+[patch.a-registry]
+a = "a"
+[patch.my-registry]
+a = "a"
+[profile.dev]
+a = "a"
+[profile.release]
+a = "a"
+[profile.bench]
+a = "a"
+[profile.custom]
+a = "a"
+"#;
+
+    let mut config = TomlFormatConfig::new();
+    config.order_sections = true;
+    config.custom_section_order = TomlSection::manifest_spec();
+
+    let mut toml = CargoToml::from_config(BEFORE.to_string(), config).unwrap();
+
+    toml.format().unwrap();
+
+    assert_eq!(toml.toml_document.to_string(), AFTER);
+}
+
+#[test]
+fn order_array_of_tables_by_name_and_keys() {
+    const BEFORE: &str = r#"[[bin]]
+test = false
+name = "c"
+path = "src/c.rs"
+
+# The first binary.
+[[bin]]
+required-features = ["a"]
+name = "a"
+
+[[bin]]
+bench = false
+name = "b"
+"#;
+
+    const AFTER: &str = r#"# The first binary.
+[[bin]]
+name = "a"
+required-features = ["a"]
+
+[[bin]]
+name = "b"
+bench = false
+
+[[bin]]
+name = "c"
+path = "src/c.rs"
+test = false
+"#;
+
+    let mut config = TomlFormatConfig::new();
+    config.order_array_of_tables_by_name = true;
+    config.order_array_of_tables_keys = true;
+
+    let mut toml = CargoToml::from_config(BEFORE.to_string(), config).unwrap();
+
+    toml.format().unwrap();
+
+    assert_eq!(toml.toml_document.to_string(), AFTER);
+}
+
+#[test]
+fn order_features_by_dependency() {
+    const BEFORE: &str = r#"[features]
+c = ["a", "dep:serde", "b"]
+a = ["b", "serde/std"]
+default = ["c"]
+b = []
+"#;
+
+    const AFTER: &str = r#"[features]
+default = ["c"]
+b = []
+a = ["serde/std", "b"]
+c = ["dep:serde", "a", "b"]
+"#;
+
+    let mut config = TomlFormatConfig::new();
+    config.order_features = Some(FeatureSort::Dependency);
+    config.order_feature_values = Some(FeatureValueSort::Grouped);
+
+    let mut toml = CargoToml::from_config(BEFORE.to_string(), config).unwrap();
+
+    toml.format().unwrap();
+
+    assert_eq!(toml.toml_document.to_string(), AFTER);
+}
+
+#[test]
+fn order_features_alphabetically() {
+    const BEFORE: &str = r#"[features]
+c = ["b", "a"]
+default = ["c"]
+a = []
+"#;
+
+    const AFTER: &str = r#"[features]
+default = ["c"]
+a = []
+c = ["a", "b"]
+"#;
+
+    let mut config = TomlFormatConfig::new();
+    config.order_features = Some(FeatureSort::Alphabetical);
+    config.order_feature_values = Some(FeatureValueSort::Alphabetical);
+
+    let mut toml = CargoToml::from_config(BEFORE.to_string(), config).unwrap();
+
+    toml.format().unwrap();
+
+    assert_eq!(toml.toml_document.to_string(), AFTER);
+}
+
+#[test]
+fn order_array_values_at_key_paths() {
+    const BEFORE: &str = r#"[package]
+keywords = ["b", "a", "b"]
+categories = ["b", "a"]
+
+[features]
+a = ["c", "b"]
+
+[workspace]
+members = [
+    "c", # The c crate.
+    "b",
+    "a" # The a crate.
+]
+exclude = [
+    "b", # The b crate.
+    "a",
+    "b",
+]
+"#;
+
+    const AFTER: &str = r#"[package]
+keywords = ["a", "b"]
+categories = ["b", "a"]
+
+[features]
+a = ["b", "c"]
+
+[workspace]
+members = [
+    "a", # The a crate.
+    "b",
+    "c" # The c crate.
+]
+exclude = [
+    "a",
+    "b", # The b crate.
+]
+"#;
+
+    let mut config = TomlFormatConfig::new();
+    config.order_array_values = vec![
+        "workspace.members".to_string(),
+        "workspace.exclude".to_string(),
+        "package.keywords".to_string(),
+        "features.*".to_string(),
+    ];
+
+    let mut toml = CargoToml::from_config(BEFORE.to_string(), config).unwrap();
+
+    toml.format().unwrap();
+
+    assert_eq!(toml.toml_document.to_string(), AFTER);
+}
+
+#[test]
+fn order_array_values_with_leading_comments() {
+    const BEFORE: &str = r#"[workspace]
+members = [
+    # The c crates.
+    "c",
+    "b", # The b crate.
+    # The a crates.
+    "a",
+    # The end.
+]
+"#;
+
+    const AFTER: &str = r#"[workspace]
+members = [
+    # The a crates.
+    "a",
+    "b", # The b crate.
+    # The c crates.
+    "c",
+    # The end.
+]
+"#;
+
+    let mut config = TomlFormatConfig::new();
+    config.order_array_values = vec!["workspace.members".to_string()];
+
+    let mut toml = CargoToml::from_config(BEFORE.to_string(), config).unwrap();
+
+    toml.format().unwrap();
+
+    assert_eq!(toml.toml_document.to_string(), AFTER);
+}
+
+#[test]
+fn order_with_natural_case_insensitive_collation() {
+    const BEFORE: &str = r#"[dependencies]
+foo-10 = "1"
+Foo-2 = "1"
+foo_1 = "1"
+
+[a]
+b-10 = {b-10=1,b-9=1}
+B-9 = "1"
+"#;
+
+    const AFTER: &str = r#"[dependencies]
+foo_1 = "1"
+Foo-2 = "1"
+foo-10 = "1"
+
+[a]
+B-9 = "1"
+b-10 = {b-9=1,b-10=1}
+"#;
+
+    let mut config = TomlFormatConfig::new();
+    config.order_dependencies_alphabetically = true;
+    config.order_table_keys_alphabetically = true;
+    config.collation = Collation {
+        case_insensitive: true,
+        natural: true,
+        dash_equals_underscore: true,
+    };
+
+    let mut toml = CargoToml::from_config(BEFORE.to_string(), config).unwrap();
+
+    toml.format().unwrap();
+
+    assert_eq!(toml.toml_document.to_string(), AFTER);
+}
+
+#[test]
+fn order_sections_keeps_file_header_and_dangling_comments() {
+    const BEFORE: &str = r#"# The file header.
+
+# The dependencies.
+[dependencies]
+a = "0.1"
+# The end of the dependencies.
+
+[package]
+name = "a"
+"#;
+
+    const AFTER: &str = r#"# The file header.
+
+[package]
+name = "a"
+
+# The dependencies.
+[dependencies]
+a = "0.1"
+# The end of the dependencies.
+"#;
+
+    let mut config = TomlFormatConfig::new();
+    config.order_sections = true;
+
+    let mut toml = CargoToml::from_config(BEFORE.to_string(), config).unwrap();
+    toml.format().unwrap();
+
     assert_eq!(toml.toml_document.to_string(), AFTER);
 }
@@ -1,7 +1,4 @@
-use std::io::Lines;
-
 use cargo_toml_fmt::{cargo_toml::CargoToml, toml_config::TomlFormatConfig};
-use toml_edit::Document;
 
 // iterate prefix lines.
 // find comments in prefix Lines
@@ -20,12 +17,12 @@ fn mytest() {
     [a]
     adep = "0.4.0""#;
 
-    let toml_2 = r#"    
+    let _toml_2 = r#"    
     [a]
     adep = "0.4.0
     # test 1"#;
 
-    let toml_3 = r#"      
+    let _toml_3 = r#"      
 [b]
 name = "b"
 
@@ -1,4 +1,4 @@
-use cargo_toml_fmt::{cargo_toml::CargoToml, toml_config::TomlFormatConfig};
+use cargo_toml_fmt::cargo_toml::CargoToml;
 
 const TOML_BEFORE: &str = r#"
 
@@ -172,9 +172,9 @@ bar = { path = "my/local/bar" }
 foo = { git = "https://github.com/example/foo" }
 
 [replace]
-"bar:1.0.2" = { path = 'my/local/bar' }
+'bar:1.0.2' = { path = 'my/local/bar' }
 
-[replace."foo:0.1.0"]
+[replace.'foo:0.1.0']
 git = 'https://github.com/example/foo'
 
 [profile.dev]
@@ -186,7 +186,7 @@ exclude = ["crates/foo", "path/to/other"]
 members = [
     "member1",
     "path/to/member2",
-    "crates/*"
+    "crates/*",
 ]
 "#;
 
@@ -225,8 +225,8 @@ members = [
 fn format_toml() {
     let mut toml = CargoToml::default(TOML_BEFORE.to_string()).unwrap();
 
-    println!("{}", toml.toml_document.to_string());
+    println!("{}", toml.toml_document);
     toml.format().unwrap();
-    println!("{}", toml.toml_document.to_string());
+    println!("{}", toml.toml_document);
     assert_eq!(toml.toml_document.to_string(), TOML_AFTER);
 }
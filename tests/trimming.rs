@@ -1,4 +1,7 @@
-use cargo_toml_fmt::{cargo_toml::CargoToml, toml_config::TomlFormatConfig};
+use cargo_toml_fmt::{
+    cargo_toml::CargoToml,
+    toml_config::{QuoteStyle, TomlFormatConfig},
+};
 
 #[test]
 fn trims_empty_spaces_section_keys() {
@@ -108,9 +111,9 @@ d = [] # A description of the package.
     config.table_formatting = true;
 
     let mut toml = CargoToml::from_config(BEFORE.to_string(), config).unwrap();
-    println!("{}", toml.toml_document.to_string());
+    println!("{}", toml.toml_document);
     toml.format().unwrap();
-    println!("{}", toml.toml_document.to_string());
+    println!("{}", toml.toml_document);
     assert_eq!(toml.toml_document.to_string(), AFTER);
 }
 
@@ -152,6 +155,7 @@ a = { a = "b" } # comment 2
 # comment 2
 b = false
 # comment 1
+
 # comment 3
 c = [""]
 d = [""] # comment 4
@@ -161,6 +165,7 @@ a = { a = "b" } # comment 2
 # comment 2
 b = false
 # comment 1
+
 # comment 3
 c = [""]
 d = [""] # comment 4
@@ -171,11 +176,149 @@ d = [""] # comment 4
 
     let mut toml = CargoToml::from_config(BEFORE.to_string(), config).unwrap();
 
-    println!("{}", toml.toml_document.to_string());
+    println!("{}", toml.toml_document);
+
+    toml.format().unwrap();
+
+    println!("{}", toml.toml_document);
+
+    assert_eq!(toml.toml_document.to_string(), AFTER);
+}
+
+#[test]
+fn trimming_keys_keeps_comments() {
+    const BEFORE: &str = r#"[a] # The a section.
+  # Comment 1
+
+  # Comment 2
+  a = 1 # Comment 3
+    # Comment 4
+    b = 2
+"#;
+
+    const AFTER: &str = r#"[a] # The a section.
+# Comment 1
+
+# Comment 2
+a = 1 # Comment 3
+# Comment 4
+b = 2
+"#;
+
+    let mut config = TomlFormatConfig::new();
+    config.trim_section_item_keys = true;
+
+    let mut toml = CargoToml::from_config(BEFORE.to_string(), config).unwrap();
+
+    toml.format().unwrap();
+
+    assert_eq!(toml.toml_document.to_string(), AFTER);
+}
+
+#[test]
+fn trimming_quotes_keeps_comments() {
+    const BEFORE: &str = r#"[a]
+# Comment 1
+"a" = 1 # Comment 2
+"b" = { "c" = 1, "d" = 2, "e f" = 3 }
+"g"."h" = 4
+"#;
+
+    const AFTER: &str = r#"[a]
+# Comment 1
+a = 1 # Comment 2
+b = { c = 1, d = 2, "e f" = 3 }
+g.h = 4
+"#;
+
+    let mut config = TomlFormatConfig::new();
+    config.trim_quotes_table_keys = true;
+
+    let mut toml = CargoToml::from_config(BEFORE.to_string(), config).unwrap();
 
     toml.format().unwrap();
 
-    println!("{}", toml.toml_document.to_string());
+    assert_eq!(toml.toml_document.to_string(), AFTER);
+}
+
+#[test]
+fn quotes_keys_only_when_required() {
+    const BEFORE: &str = r#"[target."cfg(unix)".dependencies]
+"a" = { "default-features" = false, 'foo bar' = 1 }
+
+[target.'cfg(windows)'.dependencies]
+'b' = "0.1"
+"c d" = "0.1"
+"it's" = "0.1"
+"" = "0.1"
+"#;
+
+    const AFTER: &str = r#"[target.'cfg(unix)'.dependencies]
+a = { default-features = false, 'foo bar' = 1 }
+
+[target.'cfg(windows)'.dependencies]
+b = "0.1"
+'c d' = "0.1"
+"it's" = "0.1"
+'' = "0.1"
+"#;
+
+    let mut config = TomlFormatConfig::new();
+    config.trim_quotes_table_keys = true;
+    config.key_quote_style = QuoteStyle::Single;
+
+    let mut toml = CargoToml::from_config(BEFORE.to_string(), config).unwrap();
+
+    toml.format().unwrap();
 
     assert_eq!(toml.toml_document.to_string(), AFTER);
+
+    // The formatted keys are parsed back to the names written before formatting.
+    let before = BEFORE.parse::<toml_edit::Document>().unwrap();
+    let after = toml
+        .toml_document
+        .to_string()
+        .parse::<toml_edit::Document>()
+        .unwrap();
+    assert_eq!(key_paths(after.as_table()), key_paths(before.as_table()));
+    assert!(key_paths(after.as_table()).contains(&vec![
+        "target".to_string(),
+        "cfg(unix)".to_string(),
+        "dependencies".to_string(),
+        "a".to_string(),
+        "foo bar".to_string(),
+    ]));
+}
+
+/// Returns the path of every key in the table, including the keys of sub-tables and inline tables.
+fn key_paths(table: &dyn toml_edit::TableLike) -> Vec<Vec<String>> {
+    fn visit_item(item: &toml_edit::Item, path: &[String], paths: &mut Vec<Vec<String>>) {
+        match item {
+            toml_edit::Item::Table(table) => visit_table(table, path, paths),
+            toml_edit::Item::ArrayOfTables(tables) => tables
+                .iter()
+                .for_each(|table| visit_table(table, path, paths)),
+            toml_edit::Item::Value(toml_edit::Value::InlineTable(inline_table)) => {
+                visit_table(inline_table, path, paths)
+            }
+            _ => {}
+        }
+    }
+
+    fn visit_table(
+        table: &dyn toml_edit::TableLike,
+        path: &[String],
+        paths: &mut Vec<Vec<String>>,
+    ) {
+        for (key, item) in table.iter() {
+            let mut key_path = path.to_vec();
+            key_path.push(key.to_string());
+            paths.push(key_path.clone());
+            visit_item(item, &key_path, paths);
+        }
+    }
+
+    let mut paths = vec![];
+    visit_table(table, &[], &mut paths);
+    paths
 }